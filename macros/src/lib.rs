@@ -2,18 +2,49 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(AnyView)]
+// Views implementing `core::Layout` themselves opt out of the generated leaf
+// layout with `#[view(layout)]`. Views holding child views implement
+// `view::Stack` and are marked with `#[view(container)]`, so that events are
+// delivered to their children.
+#[proc_macro_derive(AnyView, attributes(view))]
 pub fn derive_view_base(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
+    let mut custom_layout = false;
+    let mut container = false;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("view"))
+    {
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("layout") {
+                custom_layout = true;
+            } else if meta.path.is_ident("container") {
+                container = true;
+            } else {
+                return Err(meta.error("expected `layout` or `container`"));
+            }
+            Ok(())
+        });
+
+        if let Err(err) = parsed {
+            return err.to_compile_error().into();
+        }
+    }
+
     let mut expanded = quote! {
         impl #name {
             pub fn size(mut self, width: f64, height: f64) -> Self {
+                self.view_base.fixed_width = Some(width);
+                self.view_base.fixed_height = Some(height);
                 self.view_base.size.set(vello::kurbo::Size { width, height });
                 self
             }
 
             pub fn width(mut self, width: f64) -> Self {
+                self.view_base.fixed_width = Some(width);
                 self.view_base.size.set(vello::kurbo::Size {
                     width,
                     height: self.view_base.size.get().height,
@@ -22,6 +53,7 @@ pub fn derive_view_base(input: TokenStream) -> TokenStream {
             }
 
             pub fn height(mut self, height: f64) -> Self {
+                self.view_base.fixed_height = Some(height);
                 self.view_base.size.set(vello::kurbo::Size {
                     width: self.view_base.size.get().width,
                     height
@@ -29,11 +61,107 @@ pub fn derive_view_base(input: TokenStream) -> TokenStream {
                 self
             }
 
+            // Flexible dimensions, e.g. a maximum width of `f64::INFINITY`
+            // fills the width offered by the parent.
+            pub fn frame(mut self, frame: ::gpu_ui::core::Frame) -> Self {
+                self.view_base.frame = frame;
+                self
+            }
+
+            // Stacks offer their space to children with a higher priority
+            // first, the default is 0.
+            pub fn layout_priority(mut self, priority: f64) -> Self {
+                self.view_base.layout_priority = priority;
+                self
+            }
+
+            // Overrides where the guide lies within the view when aligning it
+            // with its siblings, computed from its size in points.
+            pub fn alignment_guide(
+                mut self,
+                guide: impl Into<::gpu_ui::core::Guide>,
+                compute: impl Fn(vello::kurbo::Size) -> f64 + 'static,
+            ) -> Self {
+                self.view_base.alignment_guides.push((guide.into(), Box::new(compute)));
+                self
+            }
+
+            // Number of columns and rows the view spans within a grid.
+            pub fn grid_cell_span(mut self, columns: usize, rows: usize) -> Self {
+                self.view_base.grid_span = (columns, rows);
+                self
+            }
+
+            pub fn grid_cell_alignment(mut self, alignment: ::gpu_ui::core::Alignment) -> Self {
+                self.view_base.grid_alignment = Some(alignment);
+                self
+            }
+
+            pub fn key(mut self, key: impl std::hash::Hash) -> Self {
+                self.view_base.key = Some(::gpu_ui::state::hash(&key));
+                self
+            }
+
             pub fn visible(mut self, visible: bool) -> Self {
                 self.view_base.visible = visible;
                 self
             }
 
+            // Moves the drawn view without affecting layout.
+            pub fn offset(mut self, dx: f64, dy: f64) -> Self {
+                self.view_base.offset = vello::kurbo::Vec2::new(dx, dy);
+                self
+            }
+
+            // Rotates the drawn view by the angle in radians around the anchor.
+            pub fn rotation(mut self, angle: f64, anchor: ::gpu_ui::core::UnitPoint) -> Self {
+                self.view_base.rotation = (angle, anchor);
+                self
+            }
+
+            pub fn scale_effect(
+                mut self,
+                sx: f64,
+                sy: f64,
+                anchor: ::gpu_ui::core::UnitPoint,
+            ) -> Self {
+                self.view_base.scale = (vello::kurbo::Vec2::new(sx, sy), anchor);
+                self
+            }
+
+            // Clips drawing and hit testing of the view and its children.
+            pub fn clip_shape(mut self, shape: ::gpu_ui::core::ClipShape) -> Self {
+                self.view_base.clip_shape = Some(shape);
+                self
+            }
+
+            pub fn opacity(mut self, opacity: f32) -> Self {
+                self.view_base.opacity = opacity.clamp(0.0, 1.0);
+                self
+            }
+
+            pub fn blend_mode(mut self, blend_mode: impl Into<vello::peniko::BlendMode>) -> Self {
+                self.view_base.blend_mode = Some(blend_mode.into());
+                self
+            }
+
+            // Animates changes of the view and its children whenever the value
+            // differs from the previous frame.
+            pub fn animation(
+                mut self,
+                animation: ::gpu_ui::animation::Animation,
+                value: impl std::hash::Hash,
+            ) -> Self {
+                self.view_base.animation = Some((animation, ::gpu_ui::state::hash(&value)));
+                self
+            }
+
+            // Presents the view while it's inserted into or removed from a stack.
+            pub fn transition(mut self, transition: ::gpu_ui::transition::Transition) -> Self {
+                self.view_base.transition = Some(transition);
+                self
+            }
+
             pub fn padding_top(mut self, amount: f64) -> Self {
                 self.view_base.padding_top = amount;
                 self
@@ -70,9 +198,104 @@ pub fn derive_view_base(input: TokenStream) -> TokenStream {
                 self.view_base.on_click = Some(Box::new(on_click));
                 self
             }
+
+            pub fn on_hover(mut self, on_hover: impl Fn(bool) + 'static) -> Self {
+                self.view_base.on_hover = Some(Box::new(on_hover));
+                self
+            }
+
+            pub fn on_mouse_enter(mut self, on_mouse_enter: impl Fn() + 'static) -> Self {
+                self.view_base.on_mouse_enter = Some(Box::new(on_mouse_enter));
+                self
+            }
+
+            pub fn on_mouse_leave(mut self, on_mouse_leave: impl Fn() + 'static) -> Self {
+                self.view_base.on_mouse_leave = Some(Box::new(on_mouse_leave));
+                self
+            }
+
+            pub fn on_mouse_move(
+                mut self,
+                on_mouse_move: impl Fn(vello::kurbo::Point) + 'static,
+            ) -> Self {
+                self.view_base.on_mouse_move = Some(Box::new(on_mouse_move));
+                self
+            }
+
+            pub fn on_mouse_down(
+                mut self,
+                on_mouse_down: impl Fn(vello::kurbo::Point) + 'static,
+            ) -> Self {
+                self.view_base.on_mouse_down = Some(Box::new(on_mouse_down));
+                self
+            }
+
+            pub fn on_drag(mut self, on_drag: impl Fn(::gpu_ui::event::Drag) + 'static) -> Self {
+                self.view_base.on_drag = Some(Box::new(on_drag));
+                self
+            }
+
+            pub fn focusable(mut self, focusable: bool) -> Self {
+                self.view_base.focusable = focusable;
+                self
+            }
+
+            // Focuses the view while the state is true and keeps the state in
+            // sync when focus moves.
+            pub fn focused(mut self, focused: &::gpu_ui::state::State<bool>) -> Self {
+                self.view_base.focusable = true;
+                self.view_base.focus_binding = Some(focused.clone());
+                self
+            }
+
+            // The handler returns whether it handled the key, otherwise the key
+            // bubbles up to the enclosing views.
+            pub fn on_key_down(
+                mut self,
+                on_key_down: impl Fn(&::gpu_ui::event::KeyEvent) -> bool + 'static,
+            ) -> Self {
+                self.view_base.on_key_down = Some(Box::new(on_key_down));
+                self
+            }
+
+            pub fn on_key_up(
+                mut self,
+                on_key_up: impl Fn(&::gpu_ui::event::KeyEvent) -> bool + 'static,
+            ) -> Self {
+                self.view_base.on_key_up = Some(Box::new(on_key_up));
+                self
+            }
+
+            // The handler returns whether it consumed the scroll, otherwise it
+            // bubbles up to the enclosing views.
+            pub fn on_scroll(
+                mut self,
+                on_scroll: impl Fn(&::gpu_ui::event::ScrollEvent) -> bool + 'static,
+            ) -> Self {
+                self.view_base.on_scroll = Some(Box::new(on_scroll));
+                self
+            }
+
+            // Receives input method compositions while the view is focused.
+            pub fn on_ime(mut self, on_ime: impl Fn(&::gpu_ui::event::ImeEvent) + 'static) -> Self {
+                self.view_base.on_ime = Some(Box::new(on_ime));
+                self
+            }
         }
 
         impl BaseFields for #name {
+            fn base(&self) -> &core::Base {
+                &self.view_base
+            }
+
+            fn base_mut(&mut self) -> &mut core::Base {
+                &mut self.view_base
+            }
+
+            fn id(&self) -> u64 {
+                self.view_base.id.get()
+            }
+
             fn rect(&self) -> vello::kurbo::Rect {
                 let origin = self.view_base.origin.get();
                 vello::kurbo::Rect {
@@ -144,55 +367,89 @@ pub fn derive_view_base(input: TokenStream) -> TokenStream {
         }
     };
 
-    if ["VStack", "HStack", "ZStack"].contains(&name.to_string().as_str()) {
+    if container {
         let expanded2 = quote! {
             impl core::UserEvent for #name {
                 fn mouse_down(&self, cx: core::Context) {
-                    let rect = self.rect();
-                    if (rect.x0..=rect.x1).contains(&cx.location.x) &&
-                       (rect.y0..=rect.y1).contains(&cx.location.y) {
-
-                       self.recurse_stack(|element: &Box<dyn AnyView>| {
+                    if self.hit_test(cx.location) {
+                       self.recurse_stack(|element: &dyn AnyView| {
                            element.mouse_down(cx);
                        });
 
+                       ::gpu_ui::event::press(self, cx.location);
+
                        if let Some(action) = self.on_click() {
                            action();
                        }
                     }
                 }
+
+                // Children outside of the cursor have to be visited as well
+                // to notice when they are left.
+                fn mouse_move(&self, cx: core::Context) {
+                    self.recurse_stack(|element: &dyn AnyView| {
+                        element.mouse_move(cx);
+                    });
+
+                    ::gpu_ui::event::track(self, self.hit_test(cx.location), cx.location);
+                }
+
+                fn visit_children(&self, f: &mut dyn FnMut(&dyn AnyView)) {
+                    self.recurse_stack(|element: &dyn AnyView| f(element));
+                }
+
+                fn visit_elements(&self, f: &mut dyn FnMut(&dyn AnyView)) {
+                    for element in self.elements() {
+                        f(element.as_ref());
+                    }
+                }
             }
         };
 
         expanded.extend(expanded2);
     } else {
-        let expanded2 = quote! {
-            impl core::Layout for #name {
-                fn layout(&self, cx: Context) {
-                    self.view_base.origin.set(
-                        vello::kurbo::Point {
-                            x: cx.location.x + self.padding_left(),
-                            y: cx.location.y + self.padding_top(),
-                        }
-                    )
-                }
-            }
+        if !custom_layout {
+            expanded.extend(quote! {
+                impl core::Layout for #name {}
+            });
+        }
 
+        let expanded2 = quote! {
             impl core::UserEvent for #name {
                 fn mouse_down(&self, cx: core::Context) {
-                    if let Some(action) = self.on_click() {
-                        let rect = self.rect();
-                        if (rect.x0..=rect.x1).contains(&cx.location.x) &&
-                           (rect.y0..=rect.y1).contains(&cx.location.y) {
+                    if self.hit_test(cx.location) {
+                        ::gpu_ui::event::press(self, cx.location);
+
+                        if let Some(action) = self.on_click() {
                             action();
                         }
                     }
                 }
+
+                fn mouse_move(&self, cx: core::Context) {
+                    ::gpu_ui::event::track(self, self.hit_test(cx.location), cx.location);
+                }
+
+                fn visit_children(&self, _f: &mut dyn FnMut(&dyn AnyView)) {}
+
+                fn visit_elements(&self, _f: &mut dyn FnMut(&dyn AnyView)) {}
             }
         };
 
         expanded.extend(expanded2);
     }
 
+    // Scope the imports needed by the generated code, so deriving views works
+    // from within this crate as well as from applications depending on it.
+    let expanded = quote! {
+        const _: () = {
+            use ::gpu_ui::core::{self, ui_scale, AnyView, BaseFields, Context, Draw};
+            use ::gpu_ui::vello;
+            use ::gpu_ui::view::Stack;
+
+            #expanded
+        };
+    };
+
     TokenStream::from(expanded)
 }
@@ -0,0 +1,415 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
+
+use vello::{kurbo, peniko};
+
+use crate::{event, transition};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Curve {
+    Linear,
+    // Control points of a cubic bezier from (0, 0) to (1, 1), as in CSS.
+    CubicBezier(f64, f64, f64, f64),
+    // Settles on its own time, `response` is the period of the undamped
+    // oscillation in seconds and `damping` the fraction of critical damping.
+    Spring { response: f64, damping: f64 },
+}
+
+impl Curve {
+    // Progress after the elapsed fraction of the duration, or of seconds for
+    // springs. `None` once finished.
+    fn progress(&self, elapsed: f64, duration: f64) -> Option<f64> {
+        match *self {
+            Curve::Spring { response, damping } => spring(response, damping, elapsed),
+            _ if elapsed >= duration => None,
+            Curve::Linear => Some(elapsed / duration),
+            Curve::CubicBezier(x1, y1, x2, y2) => {
+                Some(cubic_bezier(x1, y1, x2, y2, elapsed / duration))
+            }
+        }
+    }
+}
+
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+    let bezier = |a: f64, b: f64, t: f64| {
+        3.0 * a * t * (1.0 - t).powi(2) + 3.0 * b * t.powi(2) * (1.0 - t) + t.powi(3)
+    };
+
+    // The curve is monotonic in x, find the parameter for x by bisection.
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..32 {
+        let t = (low + high) / 2.0;
+        if bezier(x1, x2, t) < x {
+            low = t;
+        } else {
+            high = t;
+        }
+    }
+
+    bezier(y1, y2, (low + high) / 2.0)
+}
+
+fn spring(response: f64, damping: f64, t: f64) -> Option<f64> {
+    let omega = std::f64::consts::TAU / response.max(0.01);
+    let damping = damping.max(0.01);
+
+    // Finished once the oscillation decayed to a thousandth of the distance.
+    if (-damping * omega * t).exp() < 1e-3 {
+        return None;
+    }
+
+    let progress = if damping < 1.0 {
+        let omega_d = omega * (1.0 - damping * damping).sqrt();
+        1.0 - (-damping * omega * t).exp()
+            * ((omega_d * t).cos() + damping * omega / omega_d * (omega_d * t).sin())
+    } else {
+        1.0 - (-omega * t).exp() * (1.0 + omega * t)
+    };
+
+    Some(progress)
+}
+
+// How property changes are animated, optionally calling back once all
+// changes animated with it have finished.
+#[derive(Clone)]
+pub struct Animation {
+    curve: Curve,
+    duration: f64,
+    completion: Option<Rc<dyn Fn()>>,
+}
+
+impl Animation {
+    pub fn new(curve: Curve, duration: f64) -> Self {
+        Animation {
+            curve,
+            duration,
+            completion: None,
+        }
+    }
+
+    pub fn linear(duration: f64) -> Self {
+        Animation::new(Curve::Linear, duration)
+    }
+
+    pub fn ease_in(duration: f64) -> Self {
+        Animation::new(Curve::CubicBezier(0.42, 0.0, 1.0, 1.0), duration)
+    }
+
+    pub fn ease_out(duration: f64) -> Self {
+        Animation::new(Curve::CubicBezier(0.0, 0.0, 0.58, 1.0), duration)
+    }
+
+    pub fn ease_in_out(duration: f64) -> Self {
+        Animation::new(Curve::CubicBezier(0.42, 0.0, 0.58, 1.0), duration)
+    }
+
+    pub fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, duration: f64) -> Self {
+        Animation::new(Curve::CubicBezier(x1, y1, x2, y2), duration)
+    }
+
+    pub fn spring(response: f64, damping: f64) -> Self {
+        Animation::new(Curve::Spring { response, damping }, response)
+    }
+
+    pub fn on_complete(mut self, completion: impl Fn() + 'static) -> Self {
+        self.completion = Some(Rc::new(completion));
+        self
+    }
+}
+
+pub trait Animatable: Clone + PartialEq + 'static {
+    fn interpolate(&self, to: &Self, t: f64) -> Self;
+}
+
+impl Animatable for f64 {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Animatable for f32 {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        self + (to - self) * t as f32
+    }
+}
+
+impl Animatable for kurbo::Vec2 {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        self.lerp(*to, t)
+    }
+}
+
+impl Animatable for kurbo::Rect {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        kurbo::Rect::from_origin_size(
+            self.origin().lerp(to.origin(), t),
+            self.size().to_vec2().lerp(to.size().to_vec2(), t).to_size(),
+        )
+    }
+}
+
+impl Animatable for peniko::Color {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        let channel = |a: u8, b: u8| {
+            (a as f64 + (b as f64 - a as f64) * t)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+        peniko::Color::rgba8(
+            channel(self.r, to.r),
+            channel(self.g, to.g),
+            channel(self.b, to.b),
+            channel(self.a, to.a),
+        )
+    }
+}
+
+struct Track {
+    from: Box<dyn Any>,
+    to: Box<dyn Any>,
+    presented: Box<dyn Any>,
+    running: Option<(Animation, Instant)>,
+    live: bool,
+}
+
+impl Track {
+    fn new<T: Animatable>(value: T) -> Self {
+        Track {
+            from: Box::new(value.clone()),
+            to: Box::new(value.clone()),
+            presented: Box::new(value),
+            running: None,
+            live: true,
+        }
+    }
+}
+
+// Presented values of animated properties, by view identity and property.
+pub struct Animator {
+    now: Instant,
+    tracks: HashMap<(u64, &'static str), Track>,
+    // Set by `with_animation`, animates the changes of the next frame.
+    pending: Option<Animation>,
+    transaction: Option<Animation>,
+    // The animation the changes of each view are animated with in this frame,
+    // and the implicit animations triggered by a changed value.
+    sources: HashMap<u64, Animation>,
+    triggered: HashMap<u64, Animation>,
+    values: HashMap<u64, (u64, bool)>,
+    completions: Vec<Rc<dyn Fn()>>,
+}
+
+impl Animator {
+    fn new() -> Self {
+        Animator {
+            now: Instant::now(),
+            tracks: HashMap::new(),
+            pending: None,
+            transaction: None,
+            sources: HashMap::new(),
+            triggered: HashMap::new(),
+            values: HashMap::new(),
+            completions: vec![],
+        }
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.tracks.values().any(|track| track.running.is_some())
+    }
+}
+
+thread_local! {
+    pub static ANIMATOR: RefCell<Animator> = RefCell::new(Animator::new());
+}
+
+// Animates the property changes caused by `body`, e.g. state changes, when
+// the view tree is next rebuilt.
+pub fn with_animation<R>(animation: Animation, body: impl FnOnce() -> R) -> R {
+    ANIMATOR.with(|animator| {
+        let mut animator = animator.borrow_mut();
+        if let Some(completion) = &animation.completion {
+            animator.completions.push(completion.clone());
+        }
+        animator.pending = Some(animation);
+    });
+
+    body()
+}
+
+pub fn begin_frame() {
+    begin_frame_at(Instant::now());
+}
+
+// Starts a frame presenting values at the given time.
+pub fn begin_frame_at(now: Instant) {
+    ANIMATOR.with(|animator| {
+        let mut animator = animator.borrow_mut();
+        animator.now = now;
+        animator.transaction = animator.pending.take();
+        animator.sources.clear();
+        animator.triggered.clear();
+    });
+}
+
+// Drops the values of views that were not laid out, calls the completions of
+// finished animations and requests another frame while animating.
+pub fn end_frame() {
+    transition::end_frame();
+
+    let finished = ANIMATOR.with(|animator| {
+        let mut animator = animator.borrow_mut();
+        animator.transaction = None;
+        animator.sources.clear();
+        animator.triggered.clear();
+
+        animator
+            .tracks
+            .retain(|_, track| std::mem::take(&mut track.live));
+        animator.values.retain(|_, (_, live)| std::mem::take(live));
+
+        if animator.is_animating() {
+            event::request_frame();
+        }
+
+        let (finished, pending) = std::mem::take(&mut animator.completions)
+            .into_iter()
+            .partition(|completion| Rc::strong_count(completion) == 1);
+        animator.completions = pending;
+        finished
+    });
+
+    for completion in finished {
+        completion();
+    }
+}
+
+// Determines the animation for the changes of a view in this frame: the
+// explicit animation, the view's own implicit animation if its value changed,
+// or the one triggered by an ancestor. Returns the view whose implicit
+// animation applies to the children.
+pub fn begin_view(
+    id: u64,
+    implicit: Option<&(Animation, u64)>,
+    inherited: Option<u64>,
+) -> Option<u64> {
+    ANIMATOR.with(|animator| {
+        let animator = &mut *animator.borrow_mut();
+        let mut scope = inherited;
+
+        if let Some((animation, value)) = implicit {
+            let previous = animator.values.insert(id, (*value, true));
+            if previous.is_some_and(|(previous, _)| previous != *value) {
+                animator.triggered.insert(id, animation.clone());
+                scope = Some(id);
+            }
+        }
+
+        let source = animator
+            .transaction
+            .clone()
+            .or_else(|| scope.and_then(|scope| animator.triggered.get(&scope).cloned()));
+
+        match source {
+            Some(source) => animator.sources.insert(id, source),
+            None => animator.sources.remove(&id),
+        };
+
+        scope
+    })
+}
+
+// Animates the changes of a view that is no longer laid out like those of
+// its parent, e.g. for removal transitions.
+pub fn inherit(id: u64, parent: u64) {
+    ANIMATOR.with(|animator| {
+        let animator = &mut *animator.borrow_mut();
+        match animator.sources.get(&parent).cloned() {
+            Some(source) => animator.sources.insert(id, source),
+            None => animator.sources.remove(&id),
+        };
+    });
+}
+
+// Returns the value to present for a property of a view. Changes of the
+// target are animated from the presented value if the view is animated in
+// this frame, and applied immediately otherwise.
+pub fn animate<T: Animatable>(id: u64, property: &'static str, target: T) -> T {
+    animate_from(id, property, target.clone(), target)
+}
+
+// Like `animate`, but a property that wasn't presented before starts out at
+// the initial value if the view is animated, e.g. for insertion transitions.
+pub fn animate_from<T: Animatable>(id: u64, property: &'static str, initial: T, target: T) -> T {
+    ANIMATOR.with(|animator| {
+        let animator = &mut *animator.borrow_mut();
+        let now = animator.now;
+        let source = animator.sources.get(&id);
+
+        let track = animator.tracks.entry((id, property)).or_insert_with(|| {
+            Track::new(match source {
+                Some(_) => initial,
+                None => target.clone(),
+            })
+        });
+        track.live = true;
+
+        let (Some(to), Some(presented)) = (
+            track.to.downcast_ref::<T>(),
+            track.presented.downcast_ref::<T>(),
+        ) else {
+            *track = Track::new(target.clone());
+            return target;
+        };
+
+        if *to != target {
+            track.from = match source {
+                Some(_) => Box::new(presented.clone()),
+                None => Box::new(target.clone()),
+            };
+            track.to = Box::new(target.clone());
+            track.running = source.map(|source| (source.clone(), now));
+        }
+
+        let progress = track.running.as_ref().and_then(|(animation, start)| {
+            let elapsed = now.saturating_duration_since(*start).as_secs_f64();
+            animation.curve.progress(elapsed, animation.duration)
+        });
+
+        let presented = match progress {
+            Some(progress) => {
+                let from = track.from.downcast_ref::<T>().unwrap_or(&target);
+                from.interpolate(&target, progress)
+            }
+            None => {
+                track.running = None;
+                target
+            }
+        };
+
+        track.presented = Box::new(presented.clone());
+        presented
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_start_at_zero_and_settle_at_one() {
+        let ease = Animation::ease_in_out(1.0);
+        assert!(ease.curve.progress(0.0, 1.0).unwrap() < 1e-6);
+        assert!((ease.curve.progress(0.5, 1.0).unwrap() - 0.5).abs() < 1e-6);
+        assert!(ease.curve.progress(0.25, 1.0).unwrap() < 0.25);
+        assert_eq!(ease.curve.progress(1.0, 1.0), None);
+
+        // An underdamped spring overshoots before it settles.
+        let spring = Animation::spring(0.5, 0.3);
+        assert_eq!(spring.curve.progress(0.0, 0.5), Some(0.0));
+        assert!(spring.curve.progress(0.25, 0.5).unwrap() > 1.0);
+        assert_eq!(spring.curve.progress(5.0, 0.5), None);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::animation;
+use crate::core::{self, AnyView};
+use crate::event;
+use crate::rendering::{self, *};
+use crate::state;
+
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::event::*;
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::keyboard::*;
+
+use vello::kurbo;
+use vello::peniko::Color;
+
+// Entry point for applications, the root closure is re-evaluated on every
+// redraw to rebuild the view tree from the current state.
+//
+//     App::new(|| my_root()).title("my app").run();
+pub struct App {
+    title: String,
+    size: kurbo::Size,
+    resizable: bool,
+    background: Color,
+    root: Box<dyn Fn() -> Box<dyn AnyView>>,
+}
+
+impl App {
+    pub fn new<V: AnyView>(root: impl Fn() -> V + 'static) -> Self {
+        App {
+            title: String::from("gpu-ui"),
+            size: kurbo::Size::new(600.0, 600.0),
+            resizable: true,
+            background: Color::BLACK,
+            root: Box::new(move || Box::new(root()) as Box<dyn AnyView>),
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    // Initial inner size of the window in logical pixels.
+    pub fn size(mut self, width: f64, height: f64) -> Self {
+        self.size = kurbo::Size::new(width, height);
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = color;
+        self
+    }
+
+    // Rebuilds the view tree from the current state, dropping the state of
+    // views which are gone.
+    fn build_view_tree(&self) -> Box<dyn AnyView> {
+        animation::begin_frame();
+        state::build(|| (self.root)())
+    }
+
+    pub fn run(self) {
+        init_runloop(self);
+    }
+
+    // Renders the root view once without opening a window, see
+    // `HeadlessRenderer::new` for `use_cpu`.
+    pub fn render_headless(&self, use_cpu: bool) -> anyhow::Result<RgbaImage> {
+        let mut renderer = HeadlessRenderer::new(use_cpu)?;
+        renderer.base_color = self.background;
+
+        let view = self.build_view_tree();
+        renderer.render(
+            view.as_ref(),
+            self.size.width as u32,
+            self.size.height as u32,
+        )
+    }
+}
+
+fn init_winit_window(
+    event_loop: &ActiveEventLoop,
+    app: &App,
+) -> std::sync::Arc<winit::window::Window> {
+    let attr = winit::window::Window::default_attributes()
+        .with_inner_size(LogicalSize::new(app.size.width, app.size.height))
+        .with_resizable(app.resizable)
+        .with_active(true)
+        .with_title(app.title.clone());
+
+    let window = event_loop
+        .create_window(attr)
+        .expect("error: creating window");
+    window.set_ime_allowed(true);
+
+    std::sync::Arc::new(window)
+}
+
+// Distance scrolled per line by mouse wheels, in points.
+const SCROLL_LINE: f64 = 40.0;
+
+#[allow(unused_assignments)]
+fn init_runloop(app: App) {
+    let mut render_cx = vello::util::RenderContext::new();
+    let mut renderers: Vec<Option<vello::Renderer>> = [].into();
+    let mut render_state = RenderState::Suspended(None);
+    let mut scene = vello::Scene::new();
+    let event_loop = EventLoop::new().expect("error: creating runloop");
+    let mut view_tree = app.build_view_tree();
+
+    let mut cx = core::Context::default();
+    let mut modifiers = ModifiersState::default();
+
+    #[allow(deprecated)]
+    let result = event_loop.run(move |event, event_loop| match event {
+        winit::event::Event::Resumed => {
+            let RenderState::Suspended(cached_window) = &mut render_state else {
+                return;
+            };
+
+            let window = cached_window
+                .take()
+                .unwrap_or_else(|| init_winit_window(event_loop, &app));
+
+            let size = window.inner_size();
+            let surface_future = render_cx.create_surface(
+                window.clone(),
+                size.width,
+                size.height,
+                wgpu::PresentMode::AutoVsync,
+            );
+            let surface = pollster::block_on(surface_future).expect("error: creating surface");
+
+            renderers.resize_with(render_cx.devices.len(), || None);
+            renderers[surface.dev_id].get_or_insert_with(|| init_renderer(&render_cx, &surface));
+
+            render_state = RenderState::Active(Box::new(ActiveRenderState { window, surface }));
+            event_loop.set_control_flow(ControlFlow::Poll);
+        }
+
+        Event::Suspended => {
+            if let RenderState::Active(state) = &render_state {
+                render_state = RenderState::Suspended(Some(state.window.clone()));
+            }
+            event_loop.set_control_flow(ControlFlow::Wait);
+        }
+
+        Event::WindowEvent { event, window_id } => {
+            let render_state = match &mut render_state {
+                RenderState::Active(state) if state.window.id() == window_id => state,
+                _ => return,
+            };
+
+            match event {
+                WindowEvent::CloseRequested => event_loop.exit(),
+
+                WindowEvent::CursorMoved { position, .. } => {
+                    cx.location = kurbo::Point {
+                        x: position.x,
+                        y: position.y,
+                    };
+
+                    if event::mouse_move(view_tree.as_ref(), cx) {
+                        render_state.window.request_redraw();
+                    }
+                }
+
+                WindowEvent::CursorLeft { .. } if event::mouse_leave(view_tree.as_ref()) => {
+                    render_state.window.request_redraw();
+                }
+
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    event::mouse_down(view_tree.as_ref(), cx);
+                    render_state.window.request_redraw();
+                }
+
+                WindowEvent::MouseInput {
+                    state: ElementState::Released,
+                    button: MouseButton::Left,
+                    ..
+                } if event::mouse_up(view_tree.as_ref(), cx) => {
+                    render_state.window.request_redraw();
+                }
+
+                WindowEvent::ModifiersChanged(state) => {
+                    modifiers = state.state();
+                }
+
+                WindowEvent::KeyboardInput { event, .. } => {
+                    let key_event = event::KeyEvent {
+                        key: event.logical_key.clone(),
+                        text: event.text.as_ref().map(|text| text.to_string()),
+                        modifiers,
+                        repeat: event.repeat,
+                    };
+
+                    let redraw = match event.state {
+                        ElementState::Pressed => event::key_down(view_tree.as_ref(), &key_event),
+                        ElementState::Released => event::key_up(view_tree.as_ref(), &key_event),
+                    };
+
+                    if redraw {
+                        render_state.window.request_redraw();
+                    }
+                }
+
+                WindowEvent::MouseWheel { delta, phase, .. } => {
+                    let delta = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => {
+                            kurbo::Vec2::new(x as f64, y as f64) * SCROLL_LINE * core::ui_scale()
+                        }
+                        MouseScrollDelta::PixelDelta(position) => {
+                            kurbo::Vec2::new(position.x, position.y)
+                        }
+                    };
+
+                    let scroll = event::ScrollEvent { delta, phase };
+                    if event::scroll(view_tree.as_ref(), cx, &scroll) {
+                        render_state.window.request_redraw();
+                    }
+                }
+
+                WindowEvent::Ime(ime) => {
+                    let ime_event = match ime {
+                        Ime::Preedit(text, cursor) => event::ImeEvent::Preedit(text, cursor),
+                        Ime::Commit(text) => event::ImeEvent::Commit(text),
+                        Ime::Enabled | Ime::Disabled => return,
+                    };
+
+                    if event::ime(view_tree.as_ref(), &ime_event) {
+                        render_state.window.request_redraw();
+                    }
+                }
+
+                WindowEvent::Resized(size) => {
+                    render_cx.resize_surface(&mut render_state.surface, size.width, size.height);
+                    render_state.window.request_redraw();
+                }
+
+                WindowEvent::RedrawRequested => {
+                    scene.reset();
+                    // Drawing starts from the root, the cursor is kept for events.
+                    let cx = core::Context::default();
+
+                    let config = &render_state.surface.config;
+                    let proposal = core::Proposal::new(config.width as f64, config.height as f64);
+
+                    view_tree = app.build_view_tree();
+                    core::layout_root(view_tree.as_ref(), cx, proposal);
+                    event::sync_focus(view_tree.as_ref());
+                    core::draw(view_tree.as_ref(), cx, &mut scene);
+                    event::draw_focus_ring(view_tree.as_ref(), &mut scene);
+                    animation::end_frame();
+
+                    if event::take_frame_request() {
+                        render_state.window.request_redraw();
+                    }
+
+                    if let Some(area) = event::take_ime_area() {
+                        render_state.window.set_ime_cursor_area(
+                            PhysicalPosition::new(area.x0, area.y0),
+                            PhysicalSize::new(area.width(), area.height()),
+                        );
+                    }
+                    rendering::render(
+                        render_state,
+                        &render_cx,
+                        &scene,
+                        &mut renderers,
+                        app.background,
+                    );
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    });
+
+    println!("{:?}", result);
+}
@@ -1,7 +1,10 @@
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use lazy_static::lazy_static;
-use vello::kurbo;
+use vello::kurbo::{self, Shape};
+use vello::peniko;
+
+use crate::{animation, event, state, transition};
 
 lazy_static! {
     static ref UI_SCALE: AtomicU64 = AtomicU64::new(1.0f64.to_bits());
@@ -9,6 +12,8 @@ lazy_static! {
 
 pub fn set_ui_scale(value: f64) {
     UI_SCALE.store(value.to_bits(), Ordering::Relaxed);
+    // Sizes measured at the previous scale are stale.
+    LAYOUT_PASS.fetch_add(1, Ordering::Relaxed);
 }
 
 pub fn ui_scale() -> f64 {
@@ -19,25 +24,666 @@ pub fn ui_scale() -> f64 {
 pub struct Context {
     pub location: kurbo::Point,
     pub level: i32,
+    // Identity of the view being laid out, derived from its structural path.
+    pub id: u64,
+    // Region the view is visible in, e.g. the viewport of a scroll view.
+    pub clip: Option<Clip>,
+    // Transform from layout to window coordinates applied while drawing.
+    pub transform: kurbo::Affine,
+    // Origin of the parent, frames are animated relative to it so that
+    // children move along with an animated parent.
+    pub parent: kurbo::Point,
+    // Ancestor whose implicit animation was triggered in this frame.
+    pub animation: Option<u64>,
+}
+
+// Values of the animatable properties presented in the current frame, set
+// while placing the view.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Presentation {
+    // Offset of the animated frame from the laid out one, in pixels.
+    pub frame_offset: kurbo::Vec2,
+    pub offset: kurbo::Vec2,
+    pub rotation: f64,
+    pub scale: kurbo::Vec2,
+    pub opacity: f32,
+}
+
+impl Default for Presentation {
+    fn default() -> Self {
+        Presentation {
+            frame_offset: kurbo::Vec2::ZERO,
+            offset: kurbo::Vec2::ZERO,
+            rotation: 0.0,
+            scale: kurbo::Vec2::new(1.0, 1.0),
+            opacity: 1.0,
+        }
+    }
+}
+
+// A point relative to a view's frame, from (0, 0) at the top left to (1, 1)
+// at the bottom right.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UnitPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl UnitPoint {
+    pub const TOP_LEFT: UnitPoint = UnitPoint::new(0.0, 0.0);
+    pub const TOP: UnitPoint = UnitPoint::new(0.5, 0.0);
+    pub const TOP_RIGHT: UnitPoint = UnitPoint::new(1.0, 0.0);
+    pub const LEFT: UnitPoint = UnitPoint::new(0.0, 0.5);
+    pub const CENTER: UnitPoint = UnitPoint::new(0.5, 0.5);
+    pub const RIGHT: UnitPoint = UnitPoint::new(1.0, 0.5);
+    pub const BOTTOM_LEFT: UnitPoint = UnitPoint::new(0.0, 1.0);
+    pub const BOTTOM: UnitPoint = UnitPoint::new(0.5, 1.0);
+    pub const BOTTOM_RIGHT: UnitPoint = UnitPoint::new(1.0, 1.0);
+
+    pub const fn new(x: f64, y: f64) -> UnitPoint {
+        UnitPoint { x, y }
+    }
+
+    pub fn resolve(self, rect: kurbo::Rect) -> kurbo::Point {
+        kurbo::Point::new(
+            rect.x0 + self.x * rect.width(),
+            rect.y0 + self.y * rect.height(),
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HorizontalAlignment {
+    #[default]
+    Leading,
+    Center,
+    Trailing,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum VerticalAlignment {
+    #[default]
+    Top,
+    Center,
+    Bottom,
+    // Baseline of the first line of text, the bottom of views without text.
+    FirstBaseline,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Alignment {
+    pub horizontal: HorizontalAlignment,
+    pub vertical: VerticalAlignment,
+}
+
+impl Alignment {
+    pub const TOP_LEADING: Alignment =
+        Alignment::new(HorizontalAlignment::Leading, VerticalAlignment::Top);
+    pub const TOP: Alignment = Alignment::new(HorizontalAlignment::Center, VerticalAlignment::Top);
+    pub const TOP_TRAILING: Alignment =
+        Alignment::new(HorizontalAlignment::Trailing, VerticalAlignment::Top);
+    pub const LEADING: Alignment =
+        Alignment::new(HorizontalAlignment::Leading, VerticalAlignment::Center);
+    pub const CENTER: Alignment =
+        Alignment::new(HorizontalAlignment::Center, VerticalAlignment::Center);
+    pub const TRAILING: Alignment =
+        Alignment::new(HorizontalAlignment::Trailing, VerticalAlignment::Center);
+    pub const BOTTOM_LEADING: Alignment =
+        Alignment::new(HorizontalAlignment::Leading, VerticalAlignment::Bottom);
+    pub const BOTTOM: Alignment =
+        Alignment::new(HorizontalAlignment::Center, VerticalAlignment::Bottom);
+    pub const BOTTOM_TRAILING: Alignment =
+        Alignment::new(HorizontalAlignment::Trailing, VerticalAlignment::Bottom);
+
+    pub const fn new(horizontal: HorizontalAlignment, vertical: VerticalAlignment) -> Self {
+        Alignment {
+            horizontal,
+            vertical,
+        }
+    }
+}
+
+// Line along which siblings are aligned, at a position within each view.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Guide {
+    Horizontal(HorizontalAlignment),
+    Vertical(VerticalAlignment),
+}
+
+impl Guide {
+    // Length of the size along the axis the guide aligns views on.
+    pub fn length(self, size: kurbo::Size) -> f64 {
+        match self {
+            Guide::Horizontal(_) => size.width,
+            Guide::Vertical(_) => size.height,
+        }
+    }
+
+    // Where aligned views are placed within more space than they need, from
+    // the start at 0 to the end at 1.
+    pub fn fraction(self) -> f64 {
+        match self {
+            Guide::Horizontal(HorizontalAlignment::Leading)
+            | Guide::Vertical(VerticalAlignment::Top | VerticalAlignment::FirstBaseline) => 0.0,
+            Guide::Horizontal(HorizontalAlignment::Center)
+            | Guide::Vertical(VerticalAlignment::Center) => 0.5,
+            Guide::Horizontal(HorizontalAlignment::Trailing)
+            | Guide::Vertical(VerticalAlignment::Bottom) => 1.0,
+        }
+    }
+}
+
+impl From<HorizontalAlignment> for Guide {
+    fn from(alignment: HorizontalAlignment) -> Self {
+        Guide::Horizontal(alignment)
+    }
+}
+
+impl From<VerticalAlignment> for Guide {
+    fn from(alignment: VerticalAlignment) -> Self {
+        Guide::Vertical(alignment)
+    }
+}
+
+// Position of the guide within a view of the size chosen for the proposal,
+// both including its padding, from its top left. Custom guides set via
+// `.alignment_guide()` take precedence.
+pub fn guide(view: &dyn AnyView, guide: Guide, proposal: Proposal, size: kurbo::Size) -> f64 {
+    let custom = view
+        .base()
+        .alignment_guides
+        .iter()
+        .find(|(g, _)| *g == guide);
+    if let Some((_, compute)) = custom {
+        return compute(size / ui_scale()) * ui_scale();
+    }
+
+    match guide {
+        Guide::Horizontal(HorizontalAlignment::Leading) => 0.0,
+        Guide::Horizontal(HorizontalAlignment::Center) => size.width / 2.0,
+        Guide::Horizontal(HorizontalAlignment::Trailing) => size.width,
+        Guide::Vertical(VerticalAlignment::Top) => 0.0,
+        Guide::Vertical(VerticalAlignment::Center) => size.height / 2.0,
+        Guide::Vertical(VerticalAlignment::Bottom) => size.height,
+        Guide::Vertical(VerticalAlignment::FirstBaseline) => {
+            view.first_baseline(proposal).unwrap_or(size.height)
+        }
+    }
+}
+
+// Shapes views can be clipped to, fitted to the view's frame. Radii are in
+// points.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClipShape {
+    Rect,
+    RoundedRect(f64),
+    Ellipse,
+    Capsule,
+}
+
+impl ClipShape {
+    pub fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        match *self {
+            ClipShape::Rect => rect.to_path(0.1),
+            ClipShape::RoundedRect(radius) => {
+                rect.to_rounded_rect(radius * ui_scale()).to_path(0.1)
+            }
+            ClipShape::Ellipse => kurbo::Ellipse::from_rect(rect).to_path(0.1),
+            ClipShape::Capsule => {
+                let radius = rect.width().min(rect.height()) / 2.0;
+                rect.to_rounded_rect(radius).to_path(0.1)
+            }
+        }
+    }
+}
+
+// The visible region of a view, the intersection of the rectangular clips of
+// its ancestors and the innermost clip shape.
+//
+// Clips are kept in layout coordinates along with the transform of the
+// clipping view. Clips of ancestors under a different transform than the
+// innermost one are not taken into account for hit testing.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Clip {
+    pub bounds: kurbo::Rect,
+    pub shape: Option<(ClipShape, kurbo::Rect)>,
+    pub transform: kurbo::Affine,
+}
+
+impl Clip {
+    // Narrows an optional clip down to the shape fitted to the rect, drawn
+    // with the transform.
+    pub fn intersect(
+        clip: Option<Clip>,
+        shape: ClipShape,
+        rect: kurbo::Rect,
+        transform: kurbo::Affine,
+    ) -> Clip {
+        let clip = clip.filter(|clip| clip.transform == transform);
+        let bounds = clip.map_or(rect, |clip| clip.bounds.intersect(rect));
+        let shape = match shape {
+            ClipShape::Rect => clip.and_then(|clip| clip.shape),
+            shape => Some((shape, rect)),
+        };
+
+        Clip {
+            bounds,
+            shape,
+            transform,
+        }
+    }
+
+    // Whether the point in window coordinates is within the clip.
+    pub fn contains(&self, point: kurbo::Point) -> bool {
+        let point = self.transform.inverse() * point;
+        let bounds = self.bounds;
+
+        (bounds.x0..=bounds.x1).contains(&point.x)
+            && (bounds.y0..=bounds.y1).contains(&point.y)
+            && self
+                .shape
+                .is_none_or(|(shape, rect)| shape.path(rect).contains(point))
+    }
+}
+
+// Identifies a child by its explicit key if set, otherwise by its position
+// within the parent.
+pub fn child_id(parent: u64, child: &dyn AnyView, index: usize) -> u64 {
+    match child.base().key {
+        Some(key) => crate::state::hash(&(parent, 1u8, key)),
+        None => crate::state::hash(&(parent, 0u8, index as u64)),
+    }
 }
 
 pub trait AnyView: Draw + Layout + UserEvent + BaseFields + std::any::Any {
     fn as_any(&self) -> &dyn std::any::Any;
 }
 
-pub trait Draw {
+pub trait Draw: BaseFields {
     fn draw(&self, cx: Context, scene: &mut vello::Scene);
+
+    // Whether the point in window coordinates lies within the drawn geometry
+    // of the view.
+    fn hit_test(&self, point: kurbo::Point) -> bool {
+        let base = self.base();
+        let local = base.transform.get().inverse() * point;
+        let rect = self.rect();
+
+        (rect.x0..=rect.x1).contains(&local.x)
+            && (rect.y0..=rect.y1).contains(&local.y)
+            && base.clip.get().is_none_or(|clip| clip.contains(point))
+            && base
+                .clip_shape
+                .is_none_or(|shape| shape.path(rect).contains(local))
+            && self.contains(local)
+    }
+
+    // Whether the point in layout coordinates, within the view's rect, lies
+    // within its geometry, e.g. the path of a shape.
+    fn contains(&self, _point: kurbo::Point) -> bool {
+        true
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+// Space offered to a view by its parent, `None` along an axis asks for the
+// view's ideal size, `f64::INFINITY` for its maximum size.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Proposal {
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    // Axis of the stack proposing to its children, e.g. along which spacers
+    // grow.
+    pub axis: Option<Axis>,
+}
+
+impl Proposal {
+    pub const UNSPECIFIED: Proposal = Proposal {
+        width: None,
+        height: None,
+        axis: None,
+    };
+
+    pub fn new(width: f64, height: f64) -> Self {
+        Proposal {
+            width: Some(width),
+            height: Some(height),
+            axis: None,
+        }
+    }
+
+    pub fn inset(self, horizontal: f64, vertical: f64) -> Self {
+        Proposal {
+            width: self.width.map(|width| f64::max(width - horizontal, 0.0)),
+            height: self.height.map(|height| f64::max(height - vertical, 0.0)),
+            ..self
+        }
+    }
+
+    pub fn unwrap_or(self, size: kurbo::Size) -> kurbo::Size {
+        kurbo::Size {
+            width: self.width.unwrap_or(size.width),
+            height: self.height.unwrap_or(size.height),
+        }
+    }
+}
+
+impl From<kurbo::Size> for Proposal {
+    fn from(size: kurbo::Size) -> Self {
+        Proposal::new(size.width, size.height)
+    }
+}
+
+// Two-pass layout: parents ask their children for the size they choose for a
+// proposal, decide the final proposals and then place them. Both the proposal
+// and the returned size include the view's padding.
+//
+// The default lets the view fill the proposed space, which suits shapes.
+pub trait Layout: BaseFields {
+    fn size_that_fits(&self, proposal: Proposal) -> kurbo::Size {
+        let content = content_proposal(self, proposal);
+        resolve_size(self, proposal, content.unwrap_or(kurbo::Size::ZERO))
+    }
+
+    fn layout(&self, cx: Context, proposal: Proposal) {
+        place(self, cx, measure(self, proposal));
+    }
+
+    // Distance from the top of the view, including its padding, to the
+    // baseline of its first line of text for the proposal, if any.
+    fn first_baseline(&self, _proposal: Proposal) -> Option<f64> {
+        None
+    }
+}
+
+static LAYOUT_PASS: AtomicU64 = AtomicU64::new(0);
+
+// Lays out the root view in a new layout pass, sizes measured in earlier
+// passes are not reused.
+pub fn layout_root(view: &dyn AnyView, cx: Context, proposal: Proposal) {
+    LAYOUT_PASS.fetch_add(1, Ordering::Relaxed);
+    view.layout(cx, proposal);
+}
+
+// Size the view chooses for the proposal, memoized for the current layout
+// pass. Containers measure their children with several proposals and again
+// when placing them, which would otherwise grow exponentially with nesting.
+pub fn measure<V: Layout + ?Sized>(view: &V, proposal: Proposal) -> kurbo::Size {
+    let pass = LAYOUT_PASS.load(Ordering::Relaxed);
+    let cached = {
+        let (measured_pass, sizes) = &*view.base().measured.borrow();
+        sizes
+            .iter()
+            .find(|(measured, _)| *measured_pass == pass && *measured == proposal)
+            .map(|(_, size)| *size)
+    };
+
+    if let Some(size) = cached {
+        return size;
+    }
+
+    let size = view.size_that_fits(proposal);
+    let mut measured = view.base().measured.borrow_mut();
+    if measured.0 != pass {
+        *measured = (pass, vec![]);
+    }
+    measured.1.push((proposal, size));
+    size
+}
+
+// Flexible dimensions of a view's content in points, set via `.frame()`.
+// Proposals are clamped to the bounds, unset bounds default to the size the
+// content chooses, so that a maximum of `f64::INFINITY` takes all the space
+// offered. The ideal dimensions are used when asked for the ideal size.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub min_width: Option<f64>,
+    pub ideal_width: Option<f64>,
+    pub max_width: Option<f64>,
+    pub min_height: Option<f64>,
+    pub ideal_height: Option<f64>,
+    pub max_height: Option<f64>,
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Frame::default()
+    }
+
+    pub fn min_width(mut self, width: f64) -> Self {
+        self.min_width = Some(width);
+        self
+    }
+
+    pub fn ideal_width(mut self, width: f64) -> Self {
+        self.ideal_width = Some(width);
+        self
+    }
+
+    pub fn max_width(mut self, width: f64) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    pub fn min_height(mut self, height: f64) -> Self {
+        self.min_height = Some(height);
+        self
+    }
+
+    pub fn ideal_height(mut self, height: f64) -> Self {
+        self.ideal_height = Some(height);
+        self
+    }
+
+    pub fn max_height(mut self, height: f64) -> Self {
+        self.max_height = Some(height);
+        self
+    }
+
+    // Bounds along an axis in pixels.
+    fn width(&self) -> [Option<f64>; 3] {
+        [self.min_width, self.ideal_width, self.max_width].map(scaled)
+    }
+
+    fn height(&self) -> [Option<f64>; 3] {
+        [self.min_height, self.ideal_height, self.max_height].map(scaled)
+    }
+}
+
+fn scaled(value: Option<f64>) -> Option<f64> {
+    value.map(|value| value * ui_scale())
+}
+
+fn flexible_proposal(proposed: Option<f64>, [min, ideal, max]: [Option<f64>; 3]) -> Option<f64> {
+    match proposed {
+        Some(proposed) => {
+            let min = min.unwrap_or(0.0);
+            Some(proposed.max(min).min(max.unwrap_or(f64::INFINITY).max(min)))
+        }
+        None => ideal,
+    }
 }
 
-pub trait Layout {
-    fn layout(&self, cx: Context);
+fn flexible_size(proposed: Option<f64>, content: f64, [min, ideal, max]: [Option<f64>; 3]) -> f64 {
+    let min = min.unwrap_or(content);
+    let max = max.unwrap_or(content).max(min);
+    proposed.or(ideal).unwrap_or(content).max(min).min(max)
+}
+
+// Proposal for the content of a view, with its padding removed and explicitly
+// set dimensions taking precedence over the proposed ones.
+pub fn content_proposal<V: BaseFields + ?Sized>(view: &V, proposal: Proposal) -> Proposal {
+    let base = view.base();
+    let proposal = proposal.inset(view.padding_horizontal(), view.padding_vertical());
+
+    Proposal {
+        width: base
+            .fixed_width
+            .map(|width| width * ui_scale())
+            .or(flexible_proposal(proposal.width, base.frame.width())),
+        height: base
+            .fixed_height
+            .map(|height| height * ui_scale())
+            .or(flexible_proposal(proposal.height, base.frame.height())),
+        axis: None,
+    }
+}
+
+// Size of a view including its padding, given the proposal and the size its
+// content chose.
+pub fn resolve_size<V: BaseFields + ?Sized>(
+    view: &V,
+    proposal: Proposal,
+    content: kurbo::Size,
+) -> kurbo::Size {
+    let base = view.base();
+    let proposal = proposal.inset(view.padding_horizontal(), view.padding_vertical());
+
+    kurbo::Size {
+        width: base.fixed_width.map_or_else(
+            || flexible_size(proposal.width, content.width, base.frame.width()),
+            |width| width * ui_scale(),
+        ) + view.padding_horizontal(),
+        height: base.fixed_height.map_or_else(
+            || flexible_size(proposal.height, content.height, base.frame.height()),
+            |height| height * ui_scale(),
+        ) + view.padding_vertical(),
+    }
+}
+
+// Positions the view at the context location and stores its size without
+// padding, animating changes of its frame, opacity and transforms as well as
+// its insertion. Returns the context to lay out children in, clipped by the
+// view.
+pub fn place<V: BaseFields + ?Sized>(view: &V, cx: Context, size: kurbo::Size) -> Context {
+    let base = view.base();
+    let id = cx.id;
+    base.id.set(id);
+    base.clip.set(cx.clip);
+
+    let scope = animation::begin_view(id, base.animation.as_ref(), cx.animation);
+
+    let origin = kurbo::Point {
+        x: cx.location.x + view.padding_left(),
+        y: cx.location.y + view.padding_top(),
+    };
+    base.origin.set(origin);
+
+    let frame = kurbo::Rect::from_origin_size(
+        origin - cx.parent.to_vec2(),
+        kurbo::Size {
+            width: f64::max(size.width - view.padding_horizontal(), 0.0),
+            height: f64::max(size.height - view.padding_vertical(), 0.0),
+        },
+    );
+    let presented = animation::animate(id, "frame", frame);
+    base.size.set(presented.size() / ui_scale());
+
+    let presentation = Presentation {
+        frame_offset: presented.origin() - frame.origin(),
+        offset: animation::animate(id, "offset", base.offset),
+        rotation: animation::animate(id, "rotation", base.rotation.0),
+        scale: animation::animate(id, "scale", base.scale.0),
+        opacity: animation::animate(id, "opacity", base.opacity),
+    };
+    base.presentation
+        .set(transition::insert(view, presentation));
+
+    let transform = cx.transform * local_transform(view);
+    base.transform.set(transform);
+
+    Context {
+        clip: match base.clip_shape {
+            Some(shape) => Some(Clip::intersect(cx.clip, shape, view.rect(), transform)),
+            None => cx.clip,
+        },
+        transform,
+        parent: origin,
+        animation: scope,
+        ..cx
+    }
+}
+
+// Transform set by the offset, rotation and scale modifiers, relative to the
+// parent, and the offset of an animated frame. It doesn't affect layout.
+pub fn local_transform<V: BaseFields + ?Sized>(view: &V) -> kurbo::Affine {
+    let base = view.base();
+    let rect = view.rect();
+    let presentation = base.presentation.get();
+    let (_, rotation_anchor) = base.rotation;
+    let (_, scale_anchor) = base.scale;
+
+    let about = |anchor: UnitPoint, transform: kurbo::Affine| {
+        let anchor = anchor.resolve(rect).to_vec2();
+        kurbo::Affine::translate(anchor) * transform * kurbo::Affine::translate(-anchor)
+    };
+
+    kurbo::Affine::translate(presentation.frame_offset + presentation.offset * ui_scale())
+        * about(
+            rotation_anchor,
+            kurbo::Affine::rotate(presentation.rotation),
+        )
+        * about(
+            scale_anchor,
+            kurbo::Affine::scale_non_uniform(presentation.scale.x, presentation.scale.y),
+        )
+}
+
+// Area drawn into by layers without a clip shape.
+const UNBOUNDED: kurbo::Rect = kurbo::Rect::new(-1e6, -1e6, 1e6, 1e6);
+
+// Draws the view, within a layer applying its clip shape, opacity and blend
+// mode if set. Containers draw their children through this as well.
+pub fn draw(view: &dyn AnyView, cx: Context, scene: &mut vello::Scene) {
+    let base = view.base();
+    let opacity = base.presentation.get().opacity;
+    let cx = Context {
+        transform: cx.transform * local_transform(view),
+        ..cx
+    };
+
+    if base.clip_shape.is_none() && opacity >= 1.0 && base.blend_mode.is_none() {
+        view.draw(cx, scene);
+        return;
+    }
+
+    let shape = base
+        .clip_shape
+        .map_or_else(|| UNBOUNDED.to_path(0.1), |shape| shape.path(view.rect()));
+
+    // Plain clips don't need to be composited.
+    let blend = match base.blend_mode {
+        Some(blend) => blend,
+        None if opacity >= 1.0 => peniko::Mix::Clip.into(),
+        None => peniko::Mix::Normal.into(),
+    };
+
+    scene.push_layer(blend, opacity, cx.transform, &shape);
+    view.draw(cx, scene);
+    scene.pop_layer();
 }
 
 pub trait UserEvent {
     fn mouse_down(&self, cx: Context);
+    fn mouse_move(&self, cx: Context);
+
+    // Visits the visible child views of containers, in layout order.
+    fn visit_children(&self, f: &mut dyn FnMut(&dyn AnyView));
+
+    // Visits all elements of containers as built, including hidden ones and
+    // loops instead of their elements.
+    fn visit_elements(&self, f: &mut dyn FnMut(&dyn AnyView));
 }
 
 pub trait BaseFields {
+    fn base(&self) -> &Base;
+    fn base_mut(&mut self) -> &mut Base;
+    fn id(&self) -> u64;
     fn rect(&self) -> kurbo::Rect;
     fn origin(&self) -> kurbo::Point;
     fn size(&self) -> kurbo::Size;
@@ -55,17 +701,79 @@ pub trait BaseFields {
     fn on_click(&self) -> &Option<Box<dyn Fn()>>;
 }
 
+// Position of a custom alignment guide in points, given the view's size.
+pub type GuideAction = Box<dyn Fn(kurbo::Size) -> f64>;
+
+// Returns whether the key was handled, otherwise it bubbles up.
+pub type KeyAction = Box<dyn Fn(&event::KeyEvent) -> bool>;
+
+pub type ImeAction = Box<dyn Fn(&event::ImeEvent)>;
+
+// Returns whether the view scrolled, otherwise the scroll bubbles up.
+pub type ScrollAction = Box<dyn Fn(&event::ScrollEvent) -> bool>;
+
 pub struct Base {
+    // Result of the last layout pass.
     pub size: std::cell::Cell<kurbo::Size>,
     pub origin: std::cell::Cell<kurbo::Point>,
+    // Sizes chosen for proposals in a layout pass, see `measure`.
+    pub measured: std::cell::RefCell<(u64, Vec<(Proposal, kurbo::Size)>)>,
+    pub clip: std::cell::Cell<Option<Clip>>,
+    // Transform from layout to window coordinates, for hit testing.
+    pub transform: std::cell::Cell<kurbo::Affine>,
     pub visible: bool,
 
+    // Drawing transforms in points and radians, around anchors in the frame.
+    pub offset: kurbo::Vec2,
+    pub rotation: (f64, UnitPoint),
+    pub scale: (kurbo::Vec2, UnitPoint),
+
+    // Drawing of the view and its children within a layer.
+    pub clip_shape: Option<ClipShape>,
+    pub opacity: f32,
+    pub blend_mode: Option<peniko::BlendMode>,
+
+    // Implicit animation of changes whenever the hashed value changes.
+    pub animation: Option<(animation::Animation, u64)>,
+    pub presentation: std::cell::Cell<Presentation>,
+    pub transition: Option<transition::Transition>,
+
+    // Explicit identity set via `.key(key)` and the resulting structural identity.
+    pub key: Option<u64>,
+    pub id: std::cell::Cell<u64>,
+    // Position among the views built in the frame, see `state::resolve`.
+    pub order: u64,
+
+    // Dimensions set explicitly via modifiers, these override any proposal.
+    pub fixed_width: Option<f64>,
+    pub fixed_height: Option<f64>,
+    pub frame: Frame,
+    // Stacks offer space to children with a higher priority first.
+    pub layout_priority: f64,
+    pub alignment_guides: Vec<(Guide, GuideAction)>,
+    // Columns and rows spanned within a grid, and the alignment within them
+    // overriding the grid's.
+    pub grid_span: (usize, usize),
+    pub grid_alignment: Option<Alignment>,
+
     pub padding_top: f64,
     pub padding_bottom: f64,
     pub padding_left: f64,
     pub padding_right: f64,
 
     pub on_click: Option<Box<dyn Fn()>>,
+    pub on_hover: Option<Box<dyn Fn(bool)>>,
+    pub on_mouse_enter: Option<Box<dyn Fn()>>,
+    pub on_mouse_leave: Option<Box<dyn Fn()>>,
+    pub on_mouse_move: Option<Box<dyn Fn(kurbo::Point)>>,
+    pub on_mouse_down: Option<Box<dyn Fn(kurbo::Point)>>,
+    pub on_drag: Option<Box<dyn Fn(event::Drag)>>,
+    pub focusable: bool,
+    pub focus_binding: Option<state::State<bool>>,
+    pub on_key_down: Option<KeyAction>,
+    pub on_key_up: Option<KeyAction>,
+    pub on_ime: Option<ImeAction>,
+    pub on_scroll: Option<ScrollAction>,
 }
 
 impl Default for Base {
@@ -73,14 +781,53 @@ impl Default for Base {
         Self {
             size: std::cell::Cell::new(kurbo::Size::default()),
             origin: std::cell::Cell::new(kurbo::Point::default()),
+            measured: std::cell::RefCell::new((0, vec![])),
+            clip: std::cell::Cell::new(None),
+            transform: std::cell::Cell::new(kurbo::Affine::IDENTITY),
             visible: true,
 
+            offset: kurbo::Vec2::ZERO,
+            rotation: (0.0, UnitPoint::CENTER),
+            scale: (kurbo::Vec2::new(1.0, 1.0), UnitPoint::CENTER),
+
+            clip_shape: None,
+            opacity: 1.0,
+            blend_mode: None,
+
+            animation: None,
+            presentation: std::cell::Cell::new(Presentation::default()),
+            transition: None,
+
+            key: None,
+            id: std::cell::Cell::new(0),
+            order: state::next_view(),
+
+            fixed_width: None,
+            fixed_height: None,
+            frame: Frame::default(),
+            layout_priority: 0.0,
+            alignment_guides: vec![],
+            grid_span: (1, 1),
+            grid_alignment: None,
+
             padding_top: 0.0,
             padding_bottom: 0.0,
             padding_left: 0.0,
             padding_right: 0.0,
 
             on_click: None,
+            on_hover: None,
+            on_mouse_enter: None,
+            on_mouse_leave: None,
+            on_mouse_move: None,
+            on_mouse_down: None,
+            on_drag: None,
+            focusable: false,
+            focus_binding: None,
+            on_key_down: None,
+            on_key_up: None,
+            on_ime: None,
+            on_scroll: None,
         }
     }
 }
@@ -0,0 +1,321 @@
+use std::cell::RefCell;
+use std::ops::Range;
+
+// Caret, selection and undo history of an editable string. The text itself is
+// owned by the application, e.g. bound through a `State<String>`, and passed
+// to each edit.
+#[derive(Clone, Default, Debug)]
+pub struct Editor {
+    pub cursor: usize,
+    // The other end of the selection, equal to the cursor without selection.
+    pub anchor: usize,
+    // Uncommitted IME composition shown at the cursor, with the selected
+    // byte range within it.
+    pub preedit: Option<(String, Option<(usize, usize)>)>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    typing: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Snapshot {
+    text: String,
+    cursor: usize,
+    anchor: usize,
+}
+
+fn previous_boundary(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .char_indices()
+        .next_back()
+        .map_or(0, |(idx, _)| idx)
+}
+
+fn next_boundary(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .chars()
+        .next()
+        .map_or(offset, |c| offset + c.len_utf8())
+}
+
+fn previous_word(text: &str, offset: usize) -> usize {
+    let trimmed = text[..offset].trim_end();
+    trimmed
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(idx, c)| idx + c.len_utf8())
+}
+
+fn next_word(text: &str, offset: usize) -> usize {
+    let rest = &text[offset..];
+    let start = rest.len() - rest.trim_start().len();
+    rest[start..]
+        .find(char::is_whitespace)
+        .map_or(text.len(), |idx| offset + start + idx)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Movement {
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Home,
+    End,
+}
+
+impl Editor {
+    pub fn selection(&self) -> Range<usize> {
+        self.cursor.min(self.anchor)..self.cursor.max(self.anchor)
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.cursor != self.anchor
+    }
+
+    pub fn selected_text<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.selection()]
+    }
+
+    // Keeps the offsets valid if the text was changed from elsewhere.
+    pub fn clamp(&mut self, text: &str) {
+        let clamp = |offset: usize| {
+            let mut offset = offset.min(text.len());
+            while !text.is_char_boundary(offset) {
+                offset -= 1;
+            }
+            offset
+        };
+
+        self.cursor = clamp(self.cursor);
+        self.anchor = clamp(self.anchor);
+    }
+
+    pub fn set_cursor(&mut self, text: &str, offset: usize, extend: bool) {
+        self.cursor = offset;
+        if !extend {
+            self.anchor = offset;
+        }
+        self.typing = false;
+        self.clamp(text);
+    }
+
+    pub fn select_all(&mut self, text: &str) {
+        self.anchor = 0;
+        self.cursor = text.len();
+        self.typing = false;
+    }
+
+    pub fn move_cursor(&mut self, text: &str, movement: Movement, extend: bool) {
+        self.clamp(text);
+
+        // Without extending, moving left or right collapses the selection.
+        let offset = match movement {
+            Movement::Left if self.has_selection() && !extend => self.selection().start,
+            Movement::Right if self.has_selection() && !extend => self.selection().end,
+            Movement::Left => previous_boundary(text, self.cursor),
+            Movement::Right => next_boundary(text, self.cursor),
+            Movement::WordLeft => previous_word(text, self.cursor),
+            Movement::WordRight => next_word(text, self.cursor),
+            Movement::Home => 0,
+            Movement::End => text.len(),
+        };
+
+        self.set_cursor(text, offset, extend);
+    }
+
+    fn checkpoint(&mut self, text: &str, typing: bool) {
+        // Consecutively typed characters are undone at once.
+        if !(typing && self.typing) {
+            self.undo.push(Snapshot {
+                text: text.to_string(),
+                cursor: self.cursor,
+                anchor: self.anchor,
+            });
+        }
+        self.redo.clear();
+        self.typing = typing;
+    }
+
+    fn replace_selection(&mut self, text: &mut String, replacement: &str, typing: bool) {
+        self.clamp(text);
+        self.checkpoint(text, typing);
+
+        let selection = self.selection();
+        text.replace_range(selection.clone(), replacement);
+        self.cursor = selection.start + replacement.len();
+        self.anchor = self.cursor;
+    }
+
+    // Replaces the selection with typed text.
+    pub fn insert(&mut self, text: &mut String, input: &str) {
+        let typing = !input.contains(char::is_whitespace);
+        self.replace_selection(text, input, typing);
+    }
+
+    // Replaces the selection with pasted text, an undo step of its own.
+    pub fn paste(&mut self, text: &mut String, input: &str) {
+        self.replace_selection(text, input, false);
+    }
+
+    pub fn delete(&mut self, text: &mut String, movement: Movement) {
+        self.clamp(text);
+
+        if !self.has_selection() {
+            self.move_cursor(text, movement, true);
+        }
+
+        if self.has_selection() {
+            self.replace_selection(text, "", false);
+        }
+    }
+
+    // Removes the selection and returns it, e.g. for the clipboard.
+    pub fn cut(&mut self, text: &mut String) -> Option<String> {
+        let selected = self
+            .has_selection()
+            .then(|| self.selected_text(text).to_string());
+        if selected.is_some() {
+            self.replace_selection(text, "", false);
+        }
+        selected
+    }
+
+    pub fn undo(&mut self, text: &mut String) -> bool {
+        self.restore(text, true)
+    }
+
+    pub fn redo(&mut self, text: &mut String) -> bool {
+        self.restore(text, false)
+    }
+
+    fn restore(&mut self, text: &mut String, undo: bool) -> bool {
+        let (from, to) = if undo {
+            (&mut self.undo, &mut self.redo)
+        } else {
+            (&mut self.redo, &mut self.undo)
+        };
+
+        let Some(snapshot) = from.pop() else {
+            return false;
+        };
+
+        to.push(Snapshot {
+            text: std::mem::replace(text, snapshot.text),
+            cursor: self.cursor,
+            anchor: self.anchor,
+        });
+
+        self.cursor = snapshot.cursor;
+        self.anchor = snapshot.anchor;
+        self.typing = false;
+        true
+    }
+
+    // Applies an IME composition, an empty preedit ends it.
+    pub fn set_preedit(&mut self, preedit: &str, selection: Option<(usize, usize)>) {
+        self.preedit = (!preedit.is_empty()).then(|| (preedit.to_string(), selection));
+    }
+}
+
+struct Clipboard {
+    // The system clipboard has to be kept alive for its contents to be served
+    // on some platforms.
+    system: Option<arboard::Clipboard>,
+    // Used when the system clipboard is not available, e.g. without a display.
+    fallback: String,
+}
+
+thread_local! {
+    static CLIPBOARD: RefCell<Clipboard> = RefCell::new(Clipboard {
+        system: arboard::Clipboard::new().ok(),
+        fallback: String::new(),
+    });
+}
+
+pub fn copy(text: &str) {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+        if let Some(system) = &mut clipboard.system {
+            let _ = system.set_text(text);
+        }
+        clipboard.fallback = text.to_string();
+    });
+}
+
+pub fn paste() -> Option<String> {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+        let system = clipboard
+            .system
+            .as_mut()
+            .and_then(|system| system.get_text().ok());
+        system.or_else(|| (!clipboard.fallback.is_empty()).then(|| clipboard.fallback.clone()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_and_moves_by_characters_and_words() {
+        let mut text = "héllo world".to_string();
+        let mut editor = Editor::default();
+
+        editor.move_cursor(&text, Movement::End, false);
+        editor.move_cursor(&text, Movement::WordLeft, true);
+        assert_eq!(editor.selected_text(&text), "world");
+
+        editor.insert(&mut text, "there");
+        assert_eq!(text, "héllo there");
+
+        editor.move_cursor(&text, Movement::Home, false);
+        editor.move_cursor(&text, Movement::Right, false);
+        editor.move_cursor(&text, Movement::Right, true);
+        assert_eq!(editor.selected_text(&text), "é");
+
+        editor.delete(&mut text, Movement::Left);
+        editor.delete(&mut text, Movement::Left);
+        assert_eq!(text, "llo there");
+        assert_eq!(editor.cursor, 0);
+    }
+
+    #[test]
+    fn undo_groups_typing_and_redo_restores() {
+        let mut text = String::new();
+        let mut editor = Editor::default();
+
+        for c in ["a", "b", "c", " ", "d"] {
+            editor.insert(&mut text, c);
+        }
+        assert_eq!(text, "abc d");
+
+        assert!(editor.undo(&mut text));
+        assert_eq!(text, "abc ");
+        assert!(editor.undo(&mut text));
+        assert_eq!(text, "abc");
+        assert!(editor.undo(&mut text));
+        assert_eq!(text, "");
+        assert!(!editor.undo(&mut text));
+
+        assert!(editor.redo(&mut text));
+        assert_eq!((text.as_str(), editor.cursor), ("abc", 3));
+    }
+
+    #[test]
+    fn cut_and_paste_through_clipboard() {
+        let mut text = "copy me".to_string();
+        let mut editor = Editor::default();
+
+        editor.select_all(&text);
+        copy(&editor.cut(&mut text).unwrap());
+        assert_eq!(text, "");
+
+        let pasted = paste().unwrap();
+        editor.paste(&mut text, &pasted);
+        editor.paste(&mut text, &pasted);
+        assert_eq!(text, "copy mecopy me");
+    }
+}
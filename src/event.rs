@@ -0,0 +1,524 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::time::Instant;
+
+use vello::{kurbo, peniko};
+use winit::event::TouchPhase;
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::core::{self, ui_scale, AnyView, BaseFields};
+use crate::state;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DragPhase {
+    Started,
+    Changed,
+    Ended,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Drag {
+    pub phase: DragPhase,
+    // Where the press started and where the cursor is now, in window pixels.
+    pub start: kurbo::Point,
+    pub location: kurbo::Point,
+    pub translation: kurbo::Vec2,
+    // Pixels per second, measured between the last two cursor moves.
+    pub velocity: kurbo::Vec2,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyEvent {
+    pub key: Key,
+    // The text produced by the key, if any, e.g. for text input.
+    pub text: Option<String>,
+    pub modifiers: ModifiersState,
+    pub repeat: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImeEvent {
+    // The text being composed with the selected byte range within it, an
+    // empty text ends the composition.
+    Preedit(String, Option<(usize, usize)>),
+    Commit(String),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScrollEvent {
+    // Distance to scroll the content by in window pixels, positive values
+    // reveal content to the left and top, following `winit`.
+    pub delta: kurbo::Vec2,
+    // Trackpads report the gesture phases, mouse wheels only `Moved`.
+    pub phase: TouchPhase,
+}
+
+// The view that was pressed with an `on_drag` handler, it keeps receiving
+// the drag until release regardless of where the cursor goes.
+struct Capture {
+    id: u64,
+    start: kurbo::Point,
+    location: kurbo::Point,
+    time: Instant,
+    velocity: kurbo::Vec2,
+    started: bool,
+}
+
+// Tracks which views were under the cursor during the last mouse move, by
+// view identity, to derive enter and leave events across view tree rebuilds.
+pub struct EventManager {
+    hovered: HashSet<u64>,
+    next_hovered: HashSet<u64>,
+    capture: Option<Capture>,
+    drag: Option<Drag>,
+    focused: Option<u64>,
+    ime_area: Option<kurbo::Rect>,
+    frame_requested: bool,
+    needs_redraw: bool,
+}
+
+impl EventManager {
+    fn new() -> Self {
+        EventManager {
+            hovered: HashSet::new(),
+            next_hovered: HashSet::new(),
+            capture: None,
+            drag: None,
+            focused: None,
+            ime_area: None,
+            frame_requested: false,
+            needs_redraw: false,
+        }
+    }
+
+    pub fn is_hovered(&self, id: u64) -> bool {
+        self.hovered.contains(&id)
+    }
+
+    pub fn focused(&self) -> Option<u64> {
+        self.focused
+    }
+
+    pub fn is_dragging(&self, id: u64) -> bool {
+        self.capture
+            .as_ref()
+            .is_some_and(|capture| capture.started && capture.id == id)
+    }
+
+    fn drag(&mut self, phase: DragPhase, location: kurbo::Point) -> Option<Drag> {
+        let capture = self.capture.as_mut()?;
+
+        if phase != DragPhase::Ended {
+            let elapsed = capture.time.elapsed().as_secs_f64();
+            if elapsed > 0.0 {
+                capture.velocity = (location - capture.location) / elapsed;
+            }
+            capture.location = location;
+            capture.time = Instant::now();
+            capture.started = true;
+        }
+
+        Some(Drag {
+            phase,
+            start: capture.start,
+            location: capture.location,
+            translation: capture.location - capture.start,
+            velocity: capture.velocity,
+        })
+    }
+}
+
+thread_local! {
+    pub static EVENT_MANAGER: RefCell<EventManager> = RefCell::new(EventManager::new());
+}
+
+// Delivers a press to the view tree, capturing the innermost view under the
+// cursor that handles drags and focusing the innermost focusable one.
+pub fn mouse_down(view: &dyn AnyView, cx: core::Context) {
+    EVENT_MANAGER.with(|manager| manager.borrow_mut().capture = None);
+    view.mouse_down(cx);
+
+    fn focusable_at(view: &dyn AnyView, location: kurbo::Point) -> Option<u64> {
+        if !view.hit_test(location) {
+            return None;
+        }
+
+        let mut found = None;
+        view.visit_children(&mut |child| {
+            if let Some(id) = focusable_at(child, location) {
+                found = Some(id);
+            }
+        });
+
+        found.or_else(|| view.base().focusable.then(|| view.id()))
+    }
+
+    set_focus(view, focusable_at(view, cx.location));
+}
+
+// Ends a drag in progress, returns whether the view tree should be redrawn.
+pub fn mouse_up(view: &dyn AnyView, cx: core::Context) -> bool {
+    let drag = EVENT_MANAGER.with(|manager| {
+        let mut manager = manager.borrow_mut();
+        match &manager.capture {
+            Some(capture) if capture.started => manager.drag(DragPhase::Ended, cx.location),
+            _ => None,
+        }
+    });
+
+    let redraw = drag.is_some() && dispatch(view, cx, drag);
+    EVENT_MANAGER.with(|manager| manager.borrow_mut().capture = None);
+    redraw
+}
+
+// Delivers a cursor move to the view tree and returns whether any handler ran,
+// in which case the view tree should be redrawn.
+pub fn mouse_move(view: &dyn AnyView, cx: core::Context) -> bool {
+    let drag = EVENT_MANAGER.with(|manager| {
+        let mut manager = manager.borrow_mut();
+        let phase = match &manager.capture {
+            Some(capture) if capture.started => DragPhase::Changed,
+            _ => DragPhase::Started,
+        };
+        manager.drag(phase, cx.location)
+    });
+
+    dispatch(view, cx, drag)
+}
+
+// Leaves all views, e.g. when the cursor leaves the window.
+pub fn mouse_leave(view: &dyn AnyView) -> bool {
+    let outside = kurbo::Point::new(f64::INFINITY, f64::INFINITY);
+    dispatch(
+        view,
+        core::Context {
+            location: outside,
+            ..Default::default()
+        },
+        None,
+    )
+}
+
+fn dispatch(view: &dyn AnyView, cx: core::Context, drag: Option<Drag>) -> bool {
+    EVENT_MANAGER.with(|manager| {
+        let mut manager = manager.borrow_mut();
+        manager.next_hovered.clear();
+        manager.drag = drag;
+        manager.needs_redraw = false;
+    });
+
+    view.mouse_move(cx);
+
+    EVENT_MANAGER.with(|manager| {
+        let manager = &mut *manager.borrow_mut();
+        std::mem::swap(&mut manager.hovered, &mut manager.next_hovered);
+        manager.drag = None;
+        manager.needs_redraw
+    })
+}
+
+// Called by every view under the cursor during a press, the first view with a
+// drag handler, i.e. the innermost one, captures the pointer.
+pub fn press<V: BaseFields + ?Sized>(view: &V, location: kurbo::Point) {
+    if let Some(action) = &view.base().on_mouse_down {
+        action(location);
+    }
+
+    if view.base().on_drag.is_none() {
+        return;
+    }
+
+    EVENT_MANAGER.with(|manager| {
+        let mut manager = manager.borrow_mut();
+        if manager.capture.is_none() {
+            manager.capture = Some(Capture {
+                id: view.base().id.get(),
+                start: location,
+                location,
+                time: Instant::now(),
+                velocity: kurbo::Vec2::ZERO,
+                started: false,
+            });
+        }
+    });
+}
+
+// Called by every view during a mouse move with whether it is under the cursor.
+pub fn track<V: BaseFields + ?Sized>(view: &V, inside: bool, location: kurbo::Point) {
+    let base = view.base();
+    let id = base.id.get();
+
+    let (was_inside, drag) = EVENT_MANAGER.with(|manager| {
+        let mut manager = manager.borrow_mut();
+        if inside {
+            manager.next_hovered.insert(id);
+        }
+
+        let captured = manager.capture.as_ref().is_some_and(|c| c.id == id);
+        let drag = manager.drag.filter(|_| captured);
+        (manager.hovered.contains(&id), drag)
+    });
+
+    let mut handled = false;
+
+    if inside != was_inside {
+        let action = if inside {
+            &base.on_mouse_enter
+        } else {
+            &base.on_mouse_leave
+        };
+
+        if let Some(action) = action {
+            action();
+            handled = true;
+        }
+
+        if let Some(action) = &base.on_hover {
+            action(inside);
+            handled = true;
+        }
+    }
+
+    if inside {
+        if let Some(action) = &base.on_mouse_move {
+            action(location);
+            handled = true;
+        }
+    }
+
+    if let (Some(drag), Some(action)) = (drag, &base.on_drag) {
+        action(drag);
+        handled = true;
+    }
+
+    if handled {
+        EVENT_MANAGER.with(|manager| manager.borrow_mut().needs_redraw = true);
+    }
+}
+
+fn visit(view: &dyn AnyView, f: &mut dyn FnMut(&dyn AnyView)) {
+    f(view);
+    view.visit_children(&mut |child| visit(child, f));
+}
+
+pub fn focused() -> Option<u64> {
+    EVENT_MANAGER.with(|manager| manager.borrow().focused)
+}
+
+// Moves focus to the view with the id and updates the focus bindings of the
+// previously and newly focused views.
+pub fn set_focus(view: &dyn AnyView, id: Option<u64>) {
+    let previous =
+        EVENT_MANAGER.with(|manager| std::mem::replace(&mut manager.borrow_mut().focused, id));
+    if previous == id {
+        return;
+    }
+
+    visit(view, &mut |view| {
+        let Some(binding) = &view.base().focus_binding else {
+            return;
+        };
+
+        let focused = Some(view.id()) == id;
+        if binding.value() != focused {
+            state::callback(binding, move |value| *value = focused)();
+        }
+    });
+}
+
+// Moves focus to the next, or previous, focusable view in layout order.
+pub fn focus_next(view: &dyn AnyView, reverse: bool) {
+    let mut order = vec![];
+    visit(view, &mut |view| {
+        if view.base().focusable {
+            order.push(view.id());
+        }
+    });
+
+    if order.is_empty() {
+        return;
+    }
+
+    let current = focused().and_then(|id| order.iter().position(|&other| other == id));
+    let next = match (current, reverse) {
+        (None, false) => 0,
+        (None, true) => order.len() - 1,
+        (Some(idx), false) => (idx + 1) % order.len(),
+        (Some(idx), true) => (idx + order.len() - 1) % order.len(),
+    };
+
+    set_focus(view, Some(order[next]));
+}
+
+// Applies focus bindings changed by the application, called after layout once
+// view ids are known.
+pub fn sync_focus(view: &dyn AnyView) {
+    let current = focused();
+    let mut requested = None;
+    let mut released = false;
+
+    visit(view, &mut |view| {
+        let Some(binding) = &view.base().focus_binding else {
+            return;
+        };
+
+        let id = view.id();
+        match binding.value() {
+            true if Some(id) != current => {
+                requested.get_or_insert(id);
+            }
+            false if Some(id) == current => released = true,
+            _ => {}
+        }
+    });
+
+    if requested.is_some() || released {
+        set_focus(view, requested);
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Route {
+    NotFound,
+    Found,
+    Handled,
+}
+
+// Offers the key to the focused view, then to its enclosing views until one
+// handles it.
+fn route(view: &dyn AnyView, event: &KeyEvent, focused: Option<u64>, down: bool) -> Route {
+    let mut result = Route::NotFound;
+    view.visit_children(&mut |child| {
+        if result == Route::NotFound {
+            result = route(child, event, focused, down);
+        }
+    });
+
+    if result == Route::Handled || (result == Route::NotFound && Some(view.id()) != focused) {
+        return result;
+    }
+
+    handle_key(view, event, down)
+}
+
+fn handle_key(view: &dyn AnyView, event: &KeyEvent, down: bool) -> Route {
+    let base = view.base();
+    let action = if down {
+        &base.on_key_down
+    } else {
+        &base.on_key_up
+    };
+
+    match action {
+        Some(action) if action(event) => Route::Handled,
+        _ => Route::Found,
+    }
+}
+
+fn dispatch_key(view: &dyn AnyView, event: &KeyEvent, down: bool) -> bool {
+    match route(view, event, focused(), down) {
+        // Without a focused view the root view receives the keys.
+        Route::NotFound => handle_key(view, event, down) == Route::Handled,
+        route => route == Route::Handled,
+    }
+}
+
+// Delivers a key press, returns whether the view tree should be redrawn.
+// Unhandled Tab and Shift-Tab move focus.
+pub fn key_down(view: &dyn AnyView, event: &KeyEvent) -> bool {
+    if dispatch_key(view, event, true) {
+        return true;
+    }
+
+    if event.key == Key::Named(NamedKey::Tab) {
+        focus_next(view, event.modifiers.shift_key());
+        return true;
+    }
+
+    false
+}
+
+pub fn key_up(view: &dyn AnyView, event: &KeyEvent) -> bool {
+    dispatch_key(view, event, false)
+}
+
+// Delivers a composition to the focused view, returns whether it accepted it.
+pub fn ime(view: &dyn AnyView, event: &ImeEvent) -> bool {
+    let Some(id) = focused() else {
+        return false;
+    };
+
+    let mut handled = false;
+    visit(view, &mut |view| {
+        if let (true, Some(action)) = (view.id() == id, &view.base().on_ime) {
+            action(event);
+            handled = true;
+        }
+    });
+    handled
+}
+
+// Delivers a scroll to the innermost view under the cursor handling it,
+// returns whether the view tree should be redrawn.
+pub fn scroll(view: &dyn AnyView, cx: core::Context, event: &ScrollEvent) -> bool {
+    if !view.hit_test(cx.location) {
+        return false;
+    }
+
+    let mut handled = false;
+    view.visit_children(&mut |child| {
+        handled = handled || scroll(child, cx, event);
+    });
+
+    match &view.base().on_scroll {
+        Some(action) if !handled => action(event),
+        _ => handled,
+    }
+}
+
+// Requested while laying out or drawing by views in motion to be drawn again
+// in the next frame, e.g. scrolling with momentum.
+pub fn request_frame() {
+    EVENT_MANAGER.with(|manager| manager.borrow_mut().frame_requested = true);
+}
+
+pub fn take_frame_request() -> bool {
+    EVENT_MANAGER.with(|manager| std::mem::take(&mut manager.borrow_mut().frame_requested))
+}
+
+// Set while drawing by the focused view accepting text input, so the input
+// method can place its candidate window next to the caret.
+pub fn set_ime_area(rect: kurbo::Rect) {
+    EVENT_MANAGER.with(|manager| manager.borrow_mut().ime_area = Some(rect));
+}
+
+pub fn take_ime_area() -> Option<kurbo::Rect> {
+    EVENT_MANAGER.with(|manager| manager.borrow_mut().ime_area.take())
+}
+
+// Outlines the focused view on top of the drawn view tree.
+pub fn draw_focus_ring(view: &dyn AnyView, scene: &mut vello::Scene) {
+    let Some(id) = focused() else {
+        return;
+    };
+
+    let mut frame = None;
+    visit(view, &mut |view| {
+        if view.id() == id {
+            frame = Some((view.rect(), view.base().transform.get()));
+        }
+    });
+
+    if let Some((rect, transform)) = frame {
+        let ring = rect
+            .inflate(3.0 * ui_scale(), 3.0 * ui_scale())
+            .to_rounded_rect(4.0 * ui_scale());
+        scene.stroke(
+            &kurbo::Stroke::new(2.0 * ui_scale()),
+            transform,
+            peniko::Color::rgb8(80, 140, 255),
+            None,
+            &ring,
+        );
+    }
+}
@@ -0,0 +1,375 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use vello::{kurbo, peniko};
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::zune_core::options::DecoderOptions;
+
+use crate::core::ui_scale;
+
+// How an image is fitted into a view's frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ContentMode {
+    // Scaled to fit the frame, keeping its aspect ratio.
+    #[default]
+    Fit,
+    // Scaled to fill the frame, keeping its aspect ratio, and clipped.
+    Fill,
+    Stretch,
+    // Drawn at its size in points, and clipped.
+    Center,
+}
+
+impl ContentMode {
+    // Frame to draw content of the size in points at within the rect.
+    pub fn frame(self, rect: kurbo::Rect, size: kurbo::Size) -> kurbo::Rect {
+        let fit = rect.width() / size.width;
+        let fill = rect.height() / size.height;
+        let size = match self {
+            ContentMode::Fit => size * fit.min(fill),
+            ContentMode::Fill => size * fit.max(fill),
+            ContentMode::Stretch => rect.size(),
+            ContentMode::Center => size * ui_scale(),
+        };
+
+        kurbo::Rect::from_center_size(rect.center(), size)
+    }
+}
+
+// Sampling of images drawn at a different size than their pixels. The renderer
+// samples bilinearly, other qualities are approximated by resampling the image
+// to the drawn size.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Quality {
+    // Nearest neighbour, e.g. for pixel art.
+    Low,
+    #[default]
+    Medium,
+    // Averages the pixels of downscaled images.
+    High,
+}
+
+// Decoded RGBA image, cheap to clone and to compare.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageHandle {
+    image: peniko::Image,
+}
+
+impl ImageHandle {
+    // Decodes a PNG or JPEG file once, later loads of the path are cached.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let key = Key::Path(path.as_ref().to_path_buf());
+        cached(key, || decode(&std::fs::read(path.as_ref())?))
+    }
+
+    // Decodes PNG or JPEG data, e.g. from `include_bytes!`, cached by address.
+    pub fn from_static(bytes: &'static [u8]) -> anyhow::Result<Self> {
+        let key = Key::Static(bytes.as_ptr() as usize, bytes.len());
+        cached(key, || decode(bytes))
+    }
+
+    // Decodes PNG or JPEG data, without caching.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        decode(bytes)
+    }
+
+    // Wraps unpremultiplied 8-bit RGBA pixels, row by row.
+    pub fn from_rgba(width: u32, height: u32, data: Vec<u8>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            data.len() == width as usize * height as usize * 4,
+            "error: expected {} bytes of {width}x{height} RGBA, got {}",
+            width as usize * height as usize * 4,
+            data.len()
+        );
+
+        Ok(ImageHandle {
+            image: peniko::Image::new(
+                peniko::Blob::new(Arc::new(data)),
+                peniko::Format::Rgba8,
+                width,
+                height,
+            ),
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height
+    }
+
+    pub fn image(&self) -> &peniko::Image {
+        &self.image
+    }
+
+    // The image to draw at the size in pixels with the quality.
+    pub fn resampled(&self, width: f64, height: f64, quality: Quality) -> peniko::Image {
+        let scale_x = width / self.image.width.max(1) as f64;
+        let scale_y = height / self.image.height.max(1) as f64;
+
+        let size = match quality {
+            // Upscaling by whole pixels keeps the edges between them sharp
+            // when the renderer interpolates.
+            Quality::Low if scale_x > 1.0 || scale_y > 1.0 => (
+                self.image.width * scale_x.ceil().max(1.0) as u32,
+                self.image.height * scale_y.ceil().max(1.0) as u32,
+            ),
+            Quality::High if scale_x < 1.0 || scale_y < 1.0 => (
+                (width.ceil() as u32).clamp(1, self.image.width),
+                (height.ceil() as u32).clamp(1, self.image.height),
+            ),
+            _ => return self.image.clone(),
+        };
+
+        let key = (self.image.data.id(), quality, size);
+        IMAGES.with(|images| {
+            let mut images = images.borrow_mut();
+            if let Some(image) = images.resampled.get(&key) {
+                return image.clone();
+            }
+
+            let image = match quality {
+                Quality::Low => nearest(&self.image, size.0, size.1),
+                _ => average(&self.image, size.0, size.1),
+            };
+            if images.resampled.len() >= RESAMPLED_CACHE_LIMIT {
+                images.resampled.clear();
+            }
+            images.resampled.insert(key, image.clone());
+            image
+        })
+    }
+}
+
+impl From<ImageHandle> for crate::paint::Paint {
+    fn from(handle: ImageHandle) -> Self {
+        crate::paint::Paint::Image(handle.image)
+    }
+}
+
+// Identity of decoded assets.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Key {
+    Path(PathBuf),
+    Static(usize, usize),
+}
+
+// Number of resampled images kept around, the cache is cleared when it grows
+// beyond.
+const RESAMPLED_CACHE_LIMIT: usize = 32;
+
+#[derive(Default)]
+struct Images {
+    decoded: HashMap<Key, ImageHandle>,
+    // Resamplings of images by quality and size, so that an image drawn at
+    // several sizes isn't resampled again every frame.
+    resampled: HashMap<(u64, Quality, (u32, u32)), peniko::Image>,
+}
+
+thread_local! {
+    static IMAGES: RefCell<Images> = RefCell::new(Images::default());
+}
+
+fn cached(
+    key: Key,
+    decode: impl FnOnce() -> anyhow::Result<ImageHandle>,
+) -> anyhow::Result<ImageHandle> {
+    if let Some(handle) = IMAGES.with(|images| images.borrow().decoded.get(&key).cloned()) {
+        return Ok(handle);
+    }
+
+    let handle = decode()?;
+    IMAGES.with(|images| images.borrow_mut().decoded.insert(key, handle.clone()));
+    Ok(handle)
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_SIGNATURE: &[u8] = b"\xff\xd8\xff";
+
+fn decode(bytes: &[u8]) -> anyhow::Result<ImageHandle> {
+    if bytes.starts_with(PNG_SIGNATURE) {
+        decode_png(bytes)
+    } else if bytes.starts_with(JPEG_SIGNATURE) {
+        decode_jpeg(bytes)
+    } else {
+        anyhow::bail!("error: unsupported image format, expected PNG or JPEG")
+    }
+}
+
+fn decode_png(bytes: &[u8]) -> anyhow::Result<ImageHandle> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(
+        png::Transformations::normalize_to_color8() | png::Transformations::ALPHA,
+    );
+    let mut reader = decoder.read_info()?;
+    let mut data = vec![0; reader.output_buffer_size().unwrap_or_default()];
+    let info = reader.next_frame(&mut data)?;
+    data.truncate(info.buffer_size());
+
+    let data = match info.color_type {
+        png::ColorType::Rgba => data,
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        color_type => anyhow::bail!("error: unexpected png color type {color_type:?}"),
+    };
+
+    ImageHandle::from_rgba(info.width, info.height, data)
+}
+
+fn decode_jpeg(bytes: &[u8]) -> anyhow::Result<ImageHandle> {
+    let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::RGBA);
+    let mut decoder =
+        zune_jpeg::JpegDecoder::new_with_options(std::io::Cursor::new(bytes), options);
+    let data = decoder
+        .decode()
+        .map_err(|err| anyhow::anyhow!("error: decoding jpeg: {err:?}"))?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| anyhow::anyhow!("error: decoding jpeg headers"))?;
+
+    ImageHandle::from_rgba(info.width as u32, info.height as u32, data)
+}
+
+fn pixels(image: &peniko::Image) -> &[u8] {
+    image.data.data()
+}
+
+fn resized(data: Vec<u8>, width: u32, height: u32) -> peniko::Image {
+    peniko::Image::new(
+        peniko::Blob::new(Arc::new(data)),
+        peniko::Format::Rgba8,
+        width,
+        height,
+    )
+}
+
+fn nearest(image: &peniko::Image, width: u32, height: u32) -> peniko::Image {
+    let source = pixels(image);
+    let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+
+    for y in 0..height {
+        let sy = (y as u64 * image.height as u64 / height as u64) as usize;
+        for x in 0..width {
+            let sx = (x as u64 * image.width as u64 / width as u64) as usize;
+            let offset = (sy * image.width as usize + sx) * 4;
+            data.extend_from_slice(&source[offset..offset + 4]);
+        }
+    }
+
+    resized(data, width, height)
+}
+
+// Box filter, each pixel averages the source pixels it covers weighted by
+// alpha, so that transparent pixels don't darken the edges.
+fn average(image: &peniko::Image, width: u32, height: u32) -> peniko::Image {
+    let source = pixels(image);
+    let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+
+    let span = |idx: u32, size: u32, source: u32| {
+        let start = idx as u64 * source as u64 / size as u64;
+        let end = ((idx as u64 + 1) * source as u64).div_ceil(size as u64);
+        start as usize..end.max(start + 1) as usize
+    };
+
+    for y in 0..height {
+        let rows = span(y, height, image.height);
+        for x in 0..width {
+            let columns = span(x, width, image.width);
+            let mut sum = [0.0; 4];
+            let mut count = 0.0;
+
+            for sy in rows.clone() {
+                for sx in columns.clone() {
+                    let offset = (sy * image.width as usize + sx) * 4;
+                    let pixel = &source[offset..offset + 4];
+                    let alpha = pixel[3] as f64;
+                    for channel in 0..3 {
+                        sum[channel] += pixel[channel] as f64 * alpha;
+                    }
+                    sum[3] += alpha;
+                    count += 1.0;
+                }
+            }
+
+            let alpha = sum[3].max(f64::EPSILON);
+            data.extend_from_slice(&[
+                (sum[0] / alpha).round() as u8,
+                (sum[1] / alpha).round() as u8,
+                (sum[2] / alpha).round() as u8,
+                (sum[3] / count).round() as u8,
+            ]);
+        }
+    }
+
+    resized(data, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_png_and_caches_by_path() {
+        let path = std::env::temp_dir().join("gpu_ui_decodes_png_and_caches_by_path.png");
+        crate::rendering::RgbaImage {
+            width: 2,
+            height: 1,
+            data: vec![255, 0, 0, 255, 0, 0, 255, 128],
+        }
+        .save_png(&path)
+        .unwrap();
+
+        let handle = ImageHandle::load(&path).unwrap();
+        assert_eq!((handle.width(), handle.height()), (2, 1));
+        assert_eq!(pixels(handle.image()), [255, 0, 0, 255, 0, 0, 255, 128]);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(ImageHandle::load(&path).unwrap(), handle);
+        assert!(ImageHandle::decode(b"not an image").is_err());
+    }
+
+    #[test]
+    fn resamples_by_quality() {
+        let handle = ImageHandle::from_rgba(
+            2,
+            2,
+            vec![
+                255, 0, 0, 255, 0, 0, 255, 255, //
+                0, 255, 0, 255, 0, 0, 0, 0,
+            ],
+        )
+        .unwrap();
+
+        let upscaled = handle.resampled(5.0, 5.0, Quality::Low);
+        assert_eq!((upscaled.width, upscaled.height), (6, 6));
+        assert_eq!(&pixels(&upscaled)[12..16], [0, 0, 255, 255]);
+
+        let downscaled = handle.resampled(1.0, 1.0, Quality::High);
+        assert_eq!((downscaled.width, downscaled.height), (1, 1));
+        assert_eq!(pixels(&downscaled), [85, 85, 85, 191]);
+
+        assert_eq!(handle.resampled(1.0, 1.0, Quality::Medium), *handle.image());
+        assert!(ImageHandle::from_rgba(2, 2, vec![0; 4]).is_err());
+    }
+
+    #[test]
+    fn resampled_images_are_cached_by_size() {
+        let handle = ImageHandle::from_rgba(4, 4, vec![255; 64]).unwrap();
+
+        let thumbnail = handle.resampled(1.0, 1.0, Quality::High);
+        let preview = handle.resampled(2.0, 2.0, Quality::High);
+        assert_ne!(thumbnail.data.id(), preview.data.id());
+
+        // Drawing both again reuses their resamplings.
+        let cached = handle.resampled(1.0, 1.0, Quality::High);
+        assert_eq!(cached.data.id(), thumbnail.data.id());
+        let cached = handle.resampled(2.0, 2.0, Quality::High);
+        assert_eq!(cached.data.id(), preview.data.id());
+    }
+}
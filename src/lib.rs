@@ -0,0 +1,23 @@
+// Lets the `AnyView` derive refer to `::gpu_ui` from within this crate too.
+extern crate self as gpu_ui;
+
+pub use macros::AnyView;
+pub use vello;
+
+pub mod animation;
+pub mod app;
+pub mod core;
+pub mod editor;
+pub mod event;
+pub mod image;
+pub mod paint;
+pub mod rendering;
+#[cfg(any(test, feature = "snapshot"))]
+pub mod snapshot;
+pub mod state;
+pub mod svg;
+pub mod text;
+pub mod transition;
+pub mod view;
+
+pub use app::App;
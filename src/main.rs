@@ -1,150 +1,12 @@
-#![allow(dead_code, unused_parens)]
+#![allow(unused_parens)]
 
-use macros::AnyView;
+use gpu_ui::animation::{self, Animation};
+use gpu_ui::core::{Alignment, ClipShape, UnitPoint};
+use gpu_ui::view::*;
+use gpu_ui::{event, state, App};
 
-mod core;
-use core::*;
-
-mod view;
-use view::*;
-
-mod rendering;
-use rendering::*;
-
-mod state;
-
-use winit::dpi::LogicalSize;
-use winit::event::*;
-use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::keyboard::*;
-
-use vello::kurbo;
 use vello::peniko::Color;
-
-fn init_winit_window(event_loop: &ActiveEventLoop) -> std::sync::Arc<winit::window::Window> {
-    let attr = winit::window::Window::default_attributes()
-        .with_inner_size(LogicalSize::new(600, 600))
-        .with_resizable(true)
-        .with_active(true)
-        .with_title("gpu go brr");
-
-    std::sync::Arc::new(
-        event_loop
-            .create_window(attr)
-            .expect("error: creating window"),
-    )
-}
-
-#[allow(unused_assignments)]
-fn init_runloop() {
-    let mut render_cx = vello::util::RenderContext::new();
-    let mut renderers: Vec<Option<vello::Renderer>> = [].into();
-    let mut render_state = RenderState::Suspended(None);
-    let mut scene = vello::Scene::new();
-    let event_loop = EventLoop::new().expect("error: creating runloop");
-    let mut view_tree = ViewTree::new();
-
-    let mut cx = core::Context {
-        location: kurbo::Point { x: 0.0, y: 0.0 },
-        level: 0,
-    };
-
-    #[allow(deprecated)]
-    let result = event_loop.run(move |event, event_loop| match event {
-        winit::event::Event::Resumed => {
-            let RenderState::Suspended(cached_window) = &mut render_state else {
-                return;
-            };
-
-            let window = cached_window
-                .take()
-                .unwrap_or_else(|| init_winit_window(event_loop));
-
-            let size = window.inner_size();
-            let surface_future = render_cx.create_surface(
-                window.clone(),
-                size.width,
-                size.height,
-                wgpu::PresentMode::AutoVsync,
-            );
-            let surface = pollster::block_on(surface_future).expect("error: creating surface");
-
-            renderers.resize_with(render_cx.devices.len(), || None);
-            renderers[surface.dev_id].get_or_insert_with(|| init_renderer(&render_cx, &surface));
-
-            render_state = RenderState::Active(ActiveRenderState { window, surface });
-            event_loop.set_control_flow(ControlFlow::Poll);
-        }
-
-        Event::Suspended => {
-            if let RenderState::Active(state) = &render_state {
-                render_state = RenderState::Suspended(Some(state.window.clone()));
-            }
-            event_loop.set_control_flow(ControlFlow::Wait);
-        }
-
-        Event::WindowEvent { event, window_id } => {
-            let render_state = match &mut render_state {
-                RenderState::Active(state) if state.window.id() == window_id => state,
-                _ => return,
-            };
-
-            match event {
-                WindowEvent::CloseRequested => event_loop.exit(),
-
-                WindowEvent::CursorMoved { position, .. } => {
-                    cx.location = kurbo::Point {
-                        x: position.x,
-                        y: position.y,
-                    };
-                }
-
-                WindowEvent::MouseInput { state, button, .. } => {
-                    if button == MouseButton::Left && state == ElementState::Pressed {
-                        view_tree.mouse_down(cx);
-                        render_state.window.request_redraw();
-                    }
-                }
-
-                WindowEvent::KeyboardInput { event, .. } => {
-                    if event.state == ElementState::Pressed {
-                        match event.logical_key.as_ref() {
-                            Key::Named(NamedKey::ArrowUp) => {
-                                render_state.window.request_redraw();
-                            }
-                            Key::Named(NamedKey::ArrowDown) => {
-                                render_state.window.request_redraw();
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                WindowEvent::Resized(size) => {
-                    render_cx.resize_surface(&mut render_state.surface, size.width, size.height);
-                    render_state.window.request_redraw();
-                }
-
-                WindowEvent::RedrawRequested => {
-                    scene.reset();
-                    cx.location = kurbo::Point::default();
-
-                    state::STATE_MANAGER.with(|manager| {
-                        manager.borrow_mut().reset_id();
-                    });
-
-                    view_tree = ViewTree::new();
-                    view_tree.layout(cx);
-                    view_tree.draw(cx, &mut scene);
-                    rendering::render(render_state, &render_cx, &scene, &mut renderers);
-                }
-                _ => {}
-            }
-        }
-        _ => {}
-    });
-
-    println!("{:?}", result);
-}
+use winit::keyboard::{Key, NamedKey};
 
 pub struct ViewTree;
 
@@ -152,37 +14,53 @@ pub struct ViewTree;
 struct ViewTreeState {
     pub scale: f64,
     pub text: String,
+    pub hovered: bool,
+    pub progress: f64,
+    pub drag_start: f64,
 }
 
 impl ViewTree {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> VStack {
         let state = state::State::new(ViewTreeState {
             scale: 1.0,
             text: String::default(),
+            hovered: false,
+            progress: 0.5,
+            drag_start: 0.5,
         });
 
-        return Self::body(state);
+        let name = state::State::new(String::new());
+
+        Self::body(state, name)
     }
 
     #[rustfmt::skip]
-    fn body(state: state::State<ViewTreeState>) -> VStack {
-        let ViewTreeState { scale, .. } = state.value();
+    fn body(state: state::State<ViewTreeState>, name: state::State<String>) -> VStack {
+        let ViewTreeState { scale, text, hovered, progress, .. } = state.value();
 
         VStack::new((
             HStack::new((
                 Rectangle::default()
                     .size(100.0, 100.0)
                     .stroke(Color::rgb8(122, 122, 122), 2.0 * scale)
-                    .on_click(state::callback(&state, {
-                        |state| {
+                    .rotation((scale - 1.0) * 0.1, UnitPoint::CENTER)
+                    .on_click({
+                        let grow = state::callback(&state, |state| {
                             state.scale += 1.0;
                             println!("clicked {}", state.scale);
-                        }
-                    })),
+                        });
+                        move || animation::with_animation(Animation::spring(0.4, 0.5), &grow)
+                    }),
 
                 Circle::default()
                     .stroke(Color::rgb8(255, 255, 255), 4.0)
+                    .fill(if hovered { Color::rgb8(122, 122, 255) } else { Color::TRANSPARENT })
                     .diameter(100.0)
+                    .animation(Animation::ease_out(0.2), hovered)
+                    .on_hover(state::callback_with(&state, |state, hovered| {
+                        state.hovered = hovered;
+                    }))
                     .on_click(state::callback(&state, {
                         |state| {
                             state.scale += 1.0;
@@ -199,44 +77,114 @@ impl ViewTree {
                     Circle::default()
                         .diameter(50.0)
                         .fill(Color::rgb8(122, 122, 255))
-                        .padding_top(25.0)
-                        .padding_left(25.0)
                         .on_click(state::callback(&state, {
                             |state| {
                                 state.text += "abcd";
                                 println!("clicked {}", state.text);
                             }
                         })),
-                )),
+                ))
+                .alignment(Alignment::CENTER)
+                .clip_shape(ClipShape::RoundedRect(16.0))
+                .opacity(if hovered { 1.0 } else { 0.8 })
+                .animation(Animation::ease_in_out(0.3), hovered),
             ))
             .spacing(40.0),
 
-            HStack::new((
-                Loop::new(0..18, |idx| {
-                    VStack::new((
-                        Loop::new(0..10, |idx2| {
+            ZStack::new((
+                Rectangle::default()
+                    .size(300.0, 20.0)
+                    .stroke(Color::rgb8(122, 122, 122), 2.0),
+
+                Rectangle::default()
+                    .size(300.0 * progress, 20.0)
+                    .fill(Color::rgb8(122, 122, 255)),
+            ))
+            .on_drag(state::callback_with(&state, |state, drag: event::Drag| {
+                if drag.phase == event::DragPhase::Started {
+                    state.drag_start = state.progress;
+                }
+                state.progress = (state.drag_start + drag.translation.x / 300.0).clamp(0.0, 1.0);
+            }))
+            .focusable(true)
+            .on_key_down({
+                let state = state.clone();
+                move |event| {
+                    let step = match event.key {
+                        Key::Named(NamedKey::ArrowLeft) => -0.1,
+                        Key::Named(NamedKey::ArrowRight) => 0.1,
+                        _ => return false,
+                    };
+                    state::callback(&state, move |state| {
+                        state.progress = (state.progress + step).clamp(0.0, 1.0);
+                    })();
+                    true
+                }
+            }),
+
+            ZStack::new((
+                Rectangle::default()
+                    .size(300.0, 30.0)
+                    .stroke(Color::rgb8(122, 122, 122), 2.0),
+
+                TextField::new(&name)
+                    .placeholder("Your name")
+                    .width(290.0)
+                    .padding_top(5.0)
+                    .padding_left(5.0),
+            )),
+
+            Text::new(&format!("text: {text}"))
+                .font_size(20.0)
+                .width(520.0),
+
+            ScrollView::new((
+                Grid::new((
+                    Loop::new(0..10, |row| {
+                        Loop::new(0..9, move |column| {
                             Circle::default()
                                 .stroke(Color::rgba8(122, 122, 255, 50), 2.0)
                                 .fill(Color::rgb8(
-                                    25 * idx2 as u8,
-                                    25 * idx2 as u8,
-                                    25 * idx2 as u8,
+                                    25 * row as u8,
+                                    25 * row as u8,
+                                    25 * row as u8,
                                 ))
-                                .diameter(5.0 * (idx + 1) as f64 / 2.0)
-                        })),
-                    )
-                    .visible(idx % 2 == 0)
-                    .spacing(20.0)
-                })),
-            )
-            .spacing(20.0),
+                                .diameter(5.0 * (2 * column + 1) as f64 / 2.0)
+                        })
+                    }),
+                ))
+                .columns(vec![GridTrack::Fit; 9])
+                .spacing(20.0)
+                .alignment(Alignment::CENTER),
+            )),
         ))
-        .spacing(100.0)
+        .spacing(50.0)
         .padding_top(40.0)
         .padding_left(40.0)
     }
 }
 
 fn main() {
-    init_runloop();
+    let app = App::new(ViewTree::new)
+        .title("gpu go brr")
+        .size(600.0, 600.0);
+
+    // Renders the view tree into a PNG instead of opening a window, e.g.
+    // `gpu-ui --headless out.png` on machines without a GPU or display.
+    // `--cpu` renders on the software adapter even if a GPU is available.
+    let args: Vec<String> = std::env::args().collect();
+    let use_cpu = args.iter().any(|arg| arg == "--cpu");
+
+    match args.iter().position(|arg| arg == "--headless") {
+        Some(idx) => {
+            let path = args
+                .get(idx + 1)
+                .filter(|path| !path.starts_with("--"))
+                .map_or("headless.png", |path| path.as_str());
+            app.render_headless(use_cpu)
+                .and_then(|image| image.save_png(path))
+                .expect("error: rendering headless");
+        }
+        None => app.run(),
+    }
 }
@@ -0,0 +1,367 @@
+use vello::kurbo::{self, Shape};
+use vello::peniko::{self, Color};
+
+use crate::animation::Animatable;
+use crate::core::{ui_scale, UnitPoint};
+
+// Fill or stroke of a shape. Gradient geometry is given in unit coordinates of
+// the view's frame, from (0, 0) at the top left to (1, 1) at the bottom right,
+// so that it follows the view's `rect()`. Images are stretched over the frame,
+// patterns repeat the image at its size in points.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    Color(Color),
+    Gradient(peniko::Gradient),
+    Image(peniko::Image),
+    Pattern(peniko::Image),
+}
+
+impl Paint {
+    pub fn linear_gradient(
+        start: UnitPoint,
+        end: UnitPoint,
+        stops: impl peniko::ColorStopsSource,
+    ) -> Self {
+        Paint::Gradient(peniko::Gradient::new_linear(point(start), point(end)).with_stops(stops))
+    }
+
+    // The radius is relative to the frame, the gradient is elliptical in
+    // frames that are not square.
+    pub fn radial_gradient(
+        center: UnitPoint,
+        radius: f32,
+        stops: impl peniko::ColorStopsSource,
+    ) -> Self {
+        Paint::Gradient(peniko::Gradient::new_radial(point(center), radius).with_stops(stops))
+    }
+
+    // Angles in radians, clockwise from the positive x axis.
+    pub fn sweep_gradient(
+        center: UnitPoint,
+        start_angle: f32,
+        end_angle: f32,
+        stops: impl peniko::ColorStopsSource,
+    ) -> Self {
+        Paint::Gradient(
+            peniko::Gradient::new_sweep(point(center), start_angle, end_angle).with_stops(stops),
+        )
+    }
+
+    // Brush and brush transform to paint the rect in layout coordinates with,
+    // `None` for patterns which are drawn tile by tile.
+    pub fn brush(&self, rect: kurbo::Rect) -> Option<(peniko::Brush, Option<kurbo::Affine>)> {
+        // Degenerate brush transforms can't be inverted by the renderer.
+        let width = rect.width().max(f64::EPSILON);
+        let height = rect.height().max(f64::EPSILON);
+        let origin = kurbo::Affine::translate(rect.origin().to_vec2());
+
+        match self {
+            Paint::Color(color) => Some((peniko::Brush::Solid(*color), None)),
+            Paint::Gradient(gradient) => Some((
+                peniko::Brush::Gradient(gradient.clone()),
+                Some(origin * kurbo::Affine::scale_non_uniform(width, height)),
+            )),
+            Paint::Image(image) => Some((
+                peniko::Brush::Image(image.clone()),
+                Some(
+                    origin
+                        * kurbo::Affine::scale_non_uniform(
+                            width / image.width.max(1) as f64,
+                            height / image.height.max(1) as f64,
+                        ),
+                ),
+            )),
+            Paint::Pattern(_) => None,
+        }
+    }
+}
+
+// Fills the shape, painting relative to the rect of the view. Patterns are
+// clipped to the shape by the non-zero rule only.
+pub fn fill(
+    scene: &mut vello::Scene,
+    rule: peniko::Fill,
+    transform: kurbo::Affine,
+    paint: &Paint,
+    rect: kurbo::Rect,
+    shape: &impl kurbo::Shape,
+) {
+    match (paint, paint.brush(rect)) {
+        (_, Some((brush, brush_transform))) => {
+            scene.fill(rule, transform, &brush, brush_transform, shape);
+        }
+        (Paint::Pattern(image), None) => {
+            scene.push_layer(peniko::Mix::Clip, 1.0, transform, shape);
+            tile(scene, transform, image, rect, shape.bounding_box());
+            scene.pop_layer();
+        }
+        _ => {}
+    }
+}
+
+// Strokes the shape, aligned to the closed outline if any, painting relative
+// to the rect of the view.
+pub fn stroke(
+    scene: &mut vello::Scene,
+    style: &StrokeStyle,
+    transform: kurbo::Affine,
+    paint: &Paint,
+    rect: kurbo::Rect,
+    shape: &impl kurbo::Shape,
+    outline: Option<&kurbo::BezPath>,
+) {
+    let (style, clip) = aligned_stroke(style, outline);
+    if let Some(clip) = &clip {
+        scene.push_layer(peniko::Mix::Clip, 1.0, transform, clip);
+    }
+
+    match paint.brush(rect) {
+        Some((brush, brush_transform)) => {
+            scene.stroke(&style, transform, &brush, brush_transform, shape);
+        }
+        None => {
+            let outline = kurbo::stroke(
+                shape.path_elements(0.1),
+                &style,
+                &kurbo::StrokeOpts::default(),
+                0.1,
+            );
+            fill(
+                scene,
+                peniko::Fill::NonZero,
+                transform,
+                paint,
+                rect,
+                &outline,
+            );
+        }
+    }
+
+    if clip.is_some() {
+        scene.pop_layer();
+    }
+}
+
+// Stroke in pixels and the region to clip it to, for strokes aligned to the
+// closed outline of a shape. Strokes inside or outside of it are drawn twice as
+// wide centered on the outline, and clipped to the half on that side, which
+// keeps them aligned at corners and along slopes of any shape.
+pub fn aligned_stroke(
+    style: &StrokeStyle,
+    outline: Option<&kurbo::BezPath>,
+) -> (kurbo::Stroke, Option<kurbo::BezPath>) {
+    let mut stroke = style.to_stroke(ui_scale());
+    let Some(outline) = outline.filter(|_| style.alignment != StrokeAlignment::Center) else {
+        return (stroke, None);
+    };
+
+    let clip = match style.alignment {
+        StrokeAlignment::Outside => {
+            // Everything around the shape up to beyond the stroke's miters,
+            // winding around the shape the other way to leave it out.
+            let margin = stroke.width * style.miter_limit.max(1.0) * 2.0 + 1.0;
+            let mut clip = outline.bounding_box().inflate(margin, margin).to_path(0.1);
+            let shape = match (clip.area() > 0.0) == (outline.area() > 0.0) {
+                true => outline.reverse_subpaths(),
+                false => outline.clone(),
+            };
+            clip.extend(shape);
+            clip
+        }
+        _ => outline.clone(),
+    };
+
+    stroke.width *= 2.0;
+    (stroke, Some(clip))
+}
+
+// Repeats the image from the origin of the rect across the bounds, the
+// renderer doesn't support extending images.
+fn tile(
+    scene: &mut vello::Scene,
+    transform: kurbo::Affine,
+    image: &peniko::Image,
+    rect: kurbo::Rect,
+    bounds: kurbo::Rect,
+) {
+    let width = image.width.max(1) as f64 * ui_scale();
+    let height = image.height.max(1) as f64 * ui_scale();
+
+    let columns = ((bounds.x0 - rect.x0) / width).floor() as i64
+        ..((bounds.x1 - rect.x0) / width).ceil() as i64;
+    let rows = ((bounds.y0 - rect.y0) / height).floor() as i64
+        ..((bounds.y1 - rect.y0) / height).ceil() as i64;
+
+    for row in rows {
+        for column in columns.clone() {
+            let origin =
+                rect.origin() + kurbo::Vec2::new(column as f64 * width, row as f64 * height);
+            scene.draw_image(
+                image,
+                transform
+                    * kurbo::Affine::translate(origin.to_vec2())
+                    * kurbo::Affine::scale(ui_scale()),
+            );
+        }
+    }
+}
+
+// Where the stroke is drawn relative to the outline of a shape.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StrokeAlignment {
+    // Within the shape, so that it doesn't bleed outside the view's rect.
+    Inside,
+    #[default]
+    Center,
+    Outside,
+}
+
+// Outline of a stroke, widths and dashes are in points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f64,
+    pub join: kurbo::Join,
+    pub miter_limit: f64,
+    pub cap: kurbo::Cap,
+    // Alternating lengths of dashes and gaps, solid if empty.
+    pub dashes: Vec<f64>,
+    pub dash_offset: f64,
+    pub alignment: StrokeAlignment,
+}
+
+impl StrokeStyle {
+    pub fn new(width: f64) -> Self {
+        StrokeStyle {
+            width,
+            join: kurbo::Join::Miter,
+            miter_limit: 4.0,
+            cap: kurbo::Cap::Butt,
+            dashes: vec![],
+            dash_offset: 0.0,
+            alignment: StrokeAlignment::Center,
+        }
+    }
+
+    pub fn join(mut self, join: kurbo::Join) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn miter_limit(mut self, limit: f64) -> Self {
+        self.miter_limit = limit;
+        self
+    }
+
+    pub fn cap(mut self, cap: kurbo::Cap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn dash(mut self, dashes: impl Into<Vec<f64>>, offset: f64) -> Self {
+        self.dashes = dashes.into();
+        self.dash_offset = offset;
+        self
+    }
+
+    pub fn alignment(mut self, alignment: StrokeAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    // Stroke in pixels at the given UI scale.
+    pub fn to_stroke(&self, scale: f64) -> kurbo::Stroke {
+        kurbo::Stroke::new(self.width * scale)
+            .with_join(self.join)
+            .with_miter_limit(self.miter_limit)
+            .with_caps(self.cap)
+            .with_dashes(
+                self.dash_offset * scale,
+                self.dashes.iter().map(|length| length * scale),
+            )
+    }
+}
+
+impl From<f64> for StrokeStyle {
+    fn from(width: f64) -> Self {
+        StrokeStyle::new(width)
+    }
+}
+
+// Widths and dash offsets are interpolated, as are dash patterns of the same
+// length.
+impl Animatable for StrokeStyle {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        let dashes = match self.dashes.len() == to.dashes.len() {
+            true => self
+                .dashes
+                .iter()
+                .zip(&to.dashes)
+                .map(|(from, to)| from.interpolate(to, t))
+                .collect(),
+            false => to.dashes.clone(),
+        };
+
+        StrokeStyle {
+            width: self.width.interpolate(&to.width, t),
+            dash_offset: self.dash_offset.interpolate(&to.dash_offset, t),
+            dashes,
+            ..to.clone()
+        }
+    }
+}
+
+fn point(point: UnitPoint) -> kurbo::Point {
+    kurbo::Point::new(point.x, point.y)
+}
+
+impl From<Color> for Paint {
+    fn from(color: Color) -> Self {
+        Paint::Color(color)
+    }
+}
+
+impl From<peniko::Gradient> for Paint {
+    fn from(gradient: peniko::Gradient) -> Self {
+        Paint::Gradient(gradient)
+    }
+}
+
+impl From<peniko::Image> for Paint {
+    fn from(image: peniko::Image) -> Self {
+        Paint::Image(image)
+    }
+}
+
+// Colors and gradients with the same geometry and number of stops are
+// interpolated, other changes apply immediately.
+impl Animatable for Paint {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        match (self, to) {
+            (Paint::Color(from), Paint::Color(to)) => Paint::Color(from.interpolate(to, t)),
+            (Paint::Gradient(from), Paint::Gradient(to))
+                if from.kind == to.kind && from.stops.len() == to.stops.len() =>
+            {
+                let mut gradient = to.clone();
+                for (stop, from) in gradient.stops.iter_mut().zip(&from.stops) {
+                    stop.offset = from.offset.interpolate(&stop.offset, t);
+                    stop.color = from.color.interpolate(&stop.color, t);
+                }
+                Paint::Gradient(gradient)
+            }
+            _ => to.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strokes_scale_widths_and_dashes() {
+        let stroke = StrokeStyle::new(2.0).dash([4.0, 1.5], 1.0).to_stroke(2.0);
+        assert_eq!(stroke.width, 4.0);
+        assert_eq!(stroke.dash_pattern.as_slice(), [8.0, 3.0]);
+        assert_eq!(stroke.dash_offset, 2.0);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{animation, core, event};
 use vello::peniko::Color;
 use vello::{Renderer, RendererOptions};
 
@@ -7,7 +8,7 @@ pub struct ActiveRenderState<'s> {
 }
 
 pub enum RenderState<'s> {
-    Active(ActiveRenderState<'s>),
+    Active(Box<ActiveRenderState<'s>>),
     Suspended(Option<std::sync::Arc<winit::window::Window>>),
 }
 
@@ -31,7 +32,8 @@ pub fn render(
     render_state: &ActiveRenderState,
     render_cx: &vello::util::RenderContext,
     scene: &vello::Scene,
-    renderers: &mut Vec<Option<Renderer>>,
+    renderers: &mut [Option<Renderer>],
+    base_color: Color,
 ) {
     let surface = &render_state.surface;
     let width = surface.config.width;
@@ -48,10 +50,10 @@ pub fn render(
         .render_to_surface(
             &device_handle.device,
             &device_handle.queue,
-            &scene,
+            scene,
             &surface_texture,
             &vello::RenderParams {
-                base_color: Color::BLACK,
+                base_color,
                 width,
                 height,
                 antialiasing_method: vello::AaConfig::Msaa16,
@@ -62,3 +64,254 @@ pub fn render(
     surface_texture.present();
     device_handle.device.poll(wgpu::Maintain::Poll);
 }
+
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * self.width + x) * 4) as usize;
+        self.data[offset..offset + 4]
+            .try_into()
+            .expect("error: pixel out of bounds")
+    }
+
+    pub fn load_png(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![0; reader.output_buffer_size().unwrap_or_default()];
+        let info = reader.next_frame(&mut data)?;
+        data.truncate(info.buffer_size());
+
+        anyhow::ensure!(
+            info.color_type == png::ColorType::Rgba && info.bit_depth == png::BitDepth::Eight,
+            "error: expected 8-bit RGBA png, got {:?} {:?}",
+            info.color_type,
+            info.bit_depth
+        );
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            data,
+        })
+    }
+
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.data)?;
+        Ok(())
+    }
+}
+
+// Renders view trees into memory instead of a window surface, for machines
+// without a display. Falls back to a software adapter (e.g. llvmpipe) when no
+// GPU is available, `use_cpu` always renders on the software adapter and
+// additionally runs vello's coarse stages on the CPU.
+pub struct HeadlessRenderer {
+    pub base_color: Color,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    renderer: Renderer,
+}
+
+impl HeadlessRenderer {
+    pub fn new(use_cpu: bool) -> anyhow::Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all()),
+            ..Default::default()
+        });
+
+        let request_adapter = |force_fallback_adapter| {
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::None,
+                force_fallback_adapter,
+                compatible_surface: None,
+            }))
+        };
+
+        let adapter = match use_cpu {
+            true => request_adapter(true),
+            false => request_adapter(false).or_else(|| request_adapter(true)),
+        }
+        .ok_or_else(|| anyhow::anyhow!("error: no wgpu adapter found"))?;
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+            },
+            None,
+        ))?;
+
+        let renderer = Renderer::new(
+            &device,
+            RendererOptions {
+                surface_format: None,
+                use_cpu,
+                antialiasing_support: vello::AaSupport::area_only(),
+                num_init_threads: std::num::NonZeroUsize::new(1),
+            },
+        )
+        .map_err(|err| anyhow::anyhow!("error: initializing vello renderer: {err}"))?;
+
+        Ok(Self {
+            base_color: Color::BLACK,
+            device,
+            queue,
+            renderer,
+        })
+    }
+
+    pub fn render(
+        &mut self,
+        view: &dyn core::AnyView,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<RgbaImage> {
+        let cx = core::Context::default();
+        let mut scene = vello::Scene::new();
+        core::layout_root(view, cx, core::Proposal::new(width as f64, height as f64));
+        event::sync_focus(view);
+        core::draw(view, cx, &mut scene);
+        event::draw_focus_ring(view, &mut scene);
+        animation::end_frame();
+
+        self.render_scene(&scene, width, height)
+    }
+
+    pub fn render_scene(
+        &mut self,
+        scene: &vello::Scene,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<RgbaImage> {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("headless target"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        self.renderer
+            .render_to_texture(
+                &self.device,
+                &self.queue,
+                scene,
+                &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                &vello::RenderParams {
+                    base_color: self.base_color,
+                    width,
+                    height,
+                    antialiasing_method: vello::AaConfig::Area,
+                },
+            )
+            .map_err(|err| anyhow::anyhow!("error: rendering to texture: {err}"))?;
+
+        // Buffer rows have to be aligned for texture copies, the padding is
+        // stripped again when reading back.
+        let row_bytes = width * 4;
+        let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("headless readback"),
+            size: (padded_row_bytes * height) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+
+        self.queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let mapped = slice.get_mapped_range();
+        let mut data = Vec::with_capacity((row_bytes * height) as usize);
+        for row in mapped.chunks(padded_row_bytes as usize) {
+            data.extend_from_slice(&row[..row_bytes as usize]);
+        }
+
+        drop(mapped);
+        buffer.unmap();
+
+        Ok(RgbaImage {
+            width,
+            height,
+            data,
+        })
+    }
+}
+
+pub fn render_headless(
+    view: &dyn core::AnyView,
+    width: u32,
+    height: u32,
+    use_cpu: bool,
+) -> anyhow::Result<RgbaImage> {
+    HeadlessRenderer::new(use_cpu)?.render(view, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::*;
+
+    #[test]
+    fn cpu_path_matches_default_adapter() {
+        let view = || {
+            Circle::default()
+                .diameter(40.0)
+                .fill(Color::rgb8(122, 122, 255))
+                .padding_top(4.0)
+                .padding_left(4.0)
+        };
+
+        let cpu = render_headless(&view(), 48, 48, true).unwrap();
+        let default = render_headless(&view(), 48, 48, false).unwrap();
+        assert_eq!(cpu.pixel(24, 24), [122, 122, 255, 255]);
+
+        let (_, mismatches) = crate::snapshot::diff_images(&default, &cpu, 2);
+        assert_eq!(mismatches, 0);
+    }
+}
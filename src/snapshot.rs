@@ -0,0 +1,206 @@
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crate::core;
+use crate::rendering::{HeadlessRenderer, RgbaImage};
+
+// Creating a renderer compiles all vello shaders, share one across tests.
+fn renderer() -> &'static Mutex<HeadlessRenderer> {
+    static RENDERER: OnceLock<Mutex<HeadlessRenderer>> = OnceLock::new();
+    RENDERER.get_or_init(|| {
+        Mutex::new(HeadlessRenderer::new(false).expect("error: creating headless renderer"))
+    })
+}
+
+// Golden-image comparison of a rendered view against `snapshots/<name>.png`.
+// Missing references fail the comparison, set `UPDATE_SNAPSHOTS=1` to record
+// new references and re-record existing ones. On mismatch the actual and diff
+// images are written to `target/snapshots`.
+pub struct Snapshot {
+    name: String,
+    width: u32,
+    height: u32,
+    tolerance: u8,
+}
+
+impl Snapshot {
+    pub fn new(name: &str) -> Self {
+        Snapshot {
+            name: name.to_string(),
+            width: 200,
+            height: 200,
+            tolerance: 2,
+        }
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    // Maximum difference allowed per color channel of each pixel.
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn assert(self, view: &dyn core::AnyView) {
+        if let Err(err) = self.compare(view) {
+            panic!("{err}");
+        }
+    }
+
+    pub fn compare(&self, view: &dyn core::AnyView) -> anyhow::Result<()> {
+        let actual = renderer()
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .render(view, self.width, self.height)?;
+
+        let reference_path = snapshot_dir().join(format!("{}.png", self.name));
+        let update = std::env::var("UPDATE_SNAPSHOTS").is_ok_and(|value| value == "1");
+
+        if update {
+            std::fs::create_dir_all(snapshot_dir())?;
+            return actual.save_png(&reference_path);
+        }
+
+        if !reference_path.exists() {
+            self.save_failure(&actual, None)?;
+            anyhow::bail!(
+                "error: snapshot '{}' has no reference at {}, record it with UPDATE_SNAPSHOTS=1",
+                self.name,
+                reference_path.display()
+            );
+        }
+
+        let reference = RgbaImage::load_png(&reference_path)?;
+
+        if (reference.width, reference.height) != (actual.width, actual.height) {
+            self.save_failure(&actual, None)?;
+            anyhow::bail!(
+                "error: snapshot '{}' size mismatch, expected {}x{} got {}x{}",
+                self.name,
+                reference.width,
+                reference.height,
+                actual.width,
+                actual.height
+            );
+        }
+
+        let (diff, mismatches) = diff_images(&reference, &actual, self.tolerance);
+
+        if mismatches > 0 {
+            let dir = self.save_failure(&actual, Some(&diff))?;
+            anyhow::bail!(
+                "error: snapshot '{}' differs in {} pixels (tolerance {}), see {}",
+                self.name,
+                mismatches,
+                self.tolerance,
+                dir.display()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn save_failure(
+        &self,
+        actual: &RgbaImage,
+        diff: Option<&RgbaImage>,
+    ) -> anyhow::Result<PathBuf> {
+        let dir = failure_dir();
+        std::fs::create_dir_all(&dir)?;
+        actual.save_png(dir.join(format!("{}.actual.png", self.name)))?;
+
+        if let Some(diff) = diff {
+            diff.save_png(dir.join(format!("{}.diff.png", self.name)))?;
+        }
+
+        Ok(dir)
+    }
+}
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("snapshots")
+}
+
+fn failure_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("target")
+        .join("snapshots")
+}
+
+// Marks pixels exceeding the tolerance in red on top of a faded grayscale
+// version of the reference.
+pub fn diff_images(reference: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (RgbaImage, usize) {
+    let mut mismatches = 0;
+    let mut data = Vec::with_capacity(reference.data.len());
+
+    for (expected, actual) in reference.data.chunks(4).zip(actual.data.chunks(4)) {
+        let differs = expected
+            .iter()
+            .zip(actual)
+            .any(|(a, b)| a.abs_diff(*b) > tolerance);
+
+        if differs {
+            mismatches += 1;
+            data.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma = (expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 3;
+            let faded = (luma / 4) as u8;
+            data.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+
+    let diff = RgbaImage {
+        width: reference.width,
+        height: reference.height,
+        data,
+    };
+
+    (diff, mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]]) -> RgbaImage {
+        RgbaImage {
+            width: pixels.len() as u32,
+            height: 1,
+            data: pixels.concat(),
+        }
+    }
+
+    #[test]
+    fn diff_respects_tolerance() {
+        let reference = image(&[[10, 10, 10, 255], [200, 0, 0, 255]]);
+        let actual = image(&[[12, 9, 10, 255], [200, 0, 40, 255]]);
+
+        let (diff, mismatches) = diff_images(&reference, &actual, 2);
+        assert_eq!(mismatches, 1);
+        assert_eq!(diff.pixel(0, 0), [2, 2, 2, 255]);
+        assert_eq!(diff.pixel(1, 0), [255, 0, 0, 255]);
+
+        let (_, mismatches) = diff_images(&reference, &actual, 40);
+        assert_eq!(mismatches, 0);
+    }
+
+    #[test]
+    fn missing_reference_fails() {
+        if std::env::var("UPDATE_SNAPSHOTS").is_ok_and(|value| value == "1") {
+            return;
+        }
+
+        use crate::view::{Rectangle, Shape};
+
+        let view = Rectangle::default().fill(vello::peniko::Color::WHITE);
+        let err = Snapshot::new("missing_reference_fails")
+            .compare(&view)
+            .unwrap_err();
+        assert!(err.to_string().contains("no reference"));
+        assert!(!snapshot_dir().join("missing_reference_fails.png").exists());
+    }
+}
@@ -1,25 +1,170 @@
-use std::any::Any;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
+use crate::core::{self, AnyView};
+
+// State is first identified by the path of scopes it is created in, the
+// source location of `State::new`, its type and how often that location was
+// already hit within the same scope. Once the view tree is built, `resolve`
+// assigns each state to the view it was created for and identifies it by that
+// view's structural path instead, so that views built conditionally don't
+// shift the identity of state built after them. Whatever isn't created again
+// while building a frame is dropped.
 pub struct StateManager {
     states: HashMap<u64, Box<dyn Any>>,
-    id: u64,
+    scopes: Vec<u64>,
+    occurrences: HashMap<u64, u64>,
+    views: u64,
+    created: Vec<Created>,
+    // Identities assigned by the last resolution to the provisional ones.
+    resolved: HashMap<u64, u64>,
+}
+
+// State created while building the frame, after the given number of views.
+struct Created {
+    site: u64,
+    provisional: u64,
+    after: u64,
+    id: Rc<Cell<u64>>,
 }
 
 impl StateManager {
     fn new() -> Self {
         StateManager {
             states: HashMap::new(),
-            id: 0,
+            scopes: vec![],
+            occurrences: HashMap::new(),
+            views: 0,
+            created: vec![],
+            resolved: HashMap::new(),
         }
     }
 
-    pub fn reset_id(&mut self) {
-        self.id = 0;
+    pub fn begin_frame(&mut self) {
+        self.scopes.clear();
+        self.occurrences.clear();
+        self.views = 0;
+        self.created.clear();
+    }
+
+    pub fn end_frame(&mut self) {
+        let live: HashSet<u64> = self
+            .created
+            .iter()
+            .map(|created| created.id.get())
+            .collect();
+        self.states.retain(|id, _| live.contains(id));
+    }
+
+    pub fn push_scope(&mut self, key: u64) {
+        let parent = self.scopes.last().copied().unwrap_or_default();
+        self.scopes.push(hash(&(parent, key)));
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn site<T: 'static>(&self, location: &std::panic::Location) -> u64 {
+        let scope = self.scopes.last().copied().unwrap_or_default();
+        hash(&(
+            scope,
+            location.file(),
+            location.line(),
+            location.column(),
+            TypeId::of::<T>(),
+        ))
+    }
+
+    fn site_id<T: 'static>(&mut self, location: &std::panic::Location) -> u64 {
+        let site = self.site::<T>(location);
+        let occurrence = self.occurrences.entry(site).or_default();
+        let id = hash(&(site, *occurrence));
+        *occurrence += 1;
+        id
+    }
+
+    // Identity of new state, as resolved in the previous frame if possible.
+    fn next_id<T: 'static>(&mut self, location: &std::panic::Location) -> Rc<Cell<u64>> {
+        let site = self.site::<T>(location);
+        let provisional = self.site_id::<T>(location);
+        let id = Rc::new(Cell::new(
+            self.resolved
+                .get(&provisional)
+                .copied()
+                .unwrap_or(provisional),
+        ));
+
+        self.created.push(Created {
+            site,
+            provisional,
+            after: self.views,
+            id: id.clone(),
+        });
+        id
+    }
+
+    // Assigns the state created while building `root` to views. State created
+    // before a view but after the previous one belongs to the outermost view
+    // containing the former but not the latter, usually the view itself, and
+    // is identified by its path, see `core::child_id`. Returns whether state
+    // was read under a different identity, which requires building again.
+    pub fn resolve(&mut self, root: &dyn AnyView) -> bool {
+        // Views in the order they were built, with their paths from the root.
+        fn visit(view: &dyn AnyView, path: &mut Vec<u64>, nodes: &mut Vec<(u64, Vec<u64>)>) {
+            let parent = path.last().copied().unwrap_or_default();
+            let mut index = 0;
+            view.visit_elements(&mut |element| {
+                path.push(core::child_id(parent, element, index));
+                visit(element, path, nodes);
+                path.pop();
+                index += 1;
+            });
+            nodes.push((view.base().order, path.clone()));
+        }
+
+        let mut nodes = vec![];
+        visit(root, &mut vec![0], &mut nodes);
+        nodes.sort_by_key(|(order, _)| *order);
+
+        let mut occurrences = HashMap::new();
+        let mut stale = false;
+        self.resolved.clear();
+        for created in &self.created {
+            let next = nodes.partition_point(|(order, _)| *order < created.after);
+            let Some((_, view)) = nodes.get(next) else {
+                continue;
+            };
+
+            // Without a view built before, the state belongs to the outermost
+            // view within the root containing the next one.
+            let shared = match next.checked_sub(1) {
+                Some(previous) => view
+                    .iter()
+                    .zip(&nodes[previous].1)
+                    .take_while(|(a, b)| a == b)
+                    .count(),
+                None => 1,
+            };
+            let owner = view[shared.min(view.len() - 1)];
+
+            let occurrence = occurrences.entry((owner, created.site)).or_insert(0u64);
+            let id = hash(&(owner, created.site, *occurrence));
+            *occurrence += 1;
+
+            let guess = created.id.get();
+            stale |=
+                id != guess && (self.states.contains_key(&id) || self.states.contains_key(&guess));
+            self.resolved.insert(created.provisional, id);
+            created.id.set(id);
+        }
+        stale
     }
 
     pub fn set_state<T: 'static>(&mut self, id: u64, value: T) {
@@ -27,16 +172,63 @@ impl StateManager {
     }
 
     pub fn get_state<T: 'static + Clone>(&mut self, id: u64) -> Option<T> {
-        if let Some(state) = self.states.get(&id) {
-            return Some(
-                state
-                    .downcast_ref::<T>()
-                    .expect("error: failed downcast")
-                    .clone(),
-            );
-        }
+        self.states.get(&id)?.downcast_ref::<T>().cloned()
+    }
+}
+
+pub fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Creates the state within `f` under the given key, e.g. to give the state of
+// each element of a list an identity independent of its position. Views do
+// the same for the state belonging to them with `.key(key)`, see `resolve`.
+pub fn scope<R>(key: impl Hash, f: impl FnOnce() -> R) -> R {
+    STATE_MANAGER.with(|manager| manager.borrow_mut().push_scope(hash(&key)));
+    let result = f();
+    STATE_MANAGER.with(|manager| manager.borrow_mut().pop_scope());
+    result
+}
+
+struct Container;
+
+// Identity of a container building its elements in scopes, e.g. a loop,
+// derived from the caller's location like that of state. Keys of elements are
+// scoped by it, so that those of sibling containers don't collide.
+#[track_caller]
+pub fn container_id() -> u64 {
+    let location = std::panic::Location::caller();
+    STATE_MANAGER.with(|manager| manager.borrow_mut().site_id::<Container>(location))
+}
+
+// Counts the views built in the frame, called whenever a view is created.
+pub fn next_view() -> u64 {
+    STATE_MANAGER.with(|manager| {
+        let mut manager = manager.borrow_mut();
+        manager.views += 1;
+        manager.views - 1
+    })
+}
+
+const MAX_BUILDS: usize = 3;
+
+// Builds the view tree for a frame. Views appearing or disappearing before
+// others change the identity of their state only once the tree is resolved,
+// so the tree is built once more when state was read under a stale identity.
+pub fn build(root: impl Fn() -> Box<dyn AnyView>) -> Box<dyn AnyView> {
+    let mut attempts = 0;
+    loop {
+        STATE_MANAGER.with(|manager| manager.borrow_mut().begin_frame());
+        let view = root();
+        attempts += 1;
 
-        None
+        let stale = STATE_MANAGER.with(|manager| manager.borrow_mut().resolve(view.as_ref()));
+        if !stale || attempts == MAX_BUILDS {
+            STATE_MANAGER.with(|manager| manager.borrow_mut().end_frame());
+            return view;
+        }
     }
 }
 
@@ -56,35 +248,59 @@ where
 
         STATE_MANAGER.with(|manager| {
             let mut manager = manager.borrow_mut();
-            manager.set_state(state.id, state.data.borrow().clone());
+            manager.set_state(state.id.get(), state.data.borrow().clone());
+        })
+    }
+}
+
+type Subscribers<T> = Rc<RefCell<Vec<Box<dyn FnMut(&mut T)>>>>;
+
+// Like `callback`, for handlers receiving an argument, e.g. `on_hover`.
+pub fn callback_with<T, A>(state: &State<T>, f: impl Fn(&mut T, A)) -> impl Fn(A)
+where
+    T: Clone + 'static,
+{
+    let state = state.clone();
+
+    move |argument| {
+        f(&mut state.borrow_mut(), argument);
+        state.notify();
+
+        STATE_MANAGER.with(|manager| {
+            let mut manager = manager.borrow_mut();
+            manager.set_state(state.id.get(), state.data.borrow().clone());
         })
     }
 }
 
 pub struct State<T: 'static + Clone> {
     data: Rc<RefCell<T>>,
-    subscribers: Rc<RefCell<std::vec::Vec<Box<dyn FnMut(&mut T)>>>>,
-    id: u64,
+    subscribers: Subscribers<T>,
+    // Shared with clones, which are handed to callbacks before the identity
+    // is resolved.
+    id: Rc<Cell<u64>>,
 }
 
 impl<T: Clone + 'static> State<T> {
+    #[track_caller]
     pub fn new(value: T) -> Self {
+        let location = std::panic::Location::caller();
+
         STATE_MANAGER.with(|manager| {
             let mut manager = manager.borrow_mut();
-            let id = manager.id;
-            manager.id += 1;
-            if let Some(other) = manager.get_state::<T>(id) {
-                return State {
+            let id = manager.next_id::<T>(location);
+            if let Some(other) = manager.get_state::<T>(id.get()) {
+                State {
                     data: Rc::new(RefCell::new(other)),
                     subscribers: Rc::new(RefCell::default()),
                     id,
-                };
+                }
             } else {
-                return State {
+                State {
                     data: Rc::new(RefCell::new(value)),
                     subscribers: Rc::new(RefCell::default()),
                     id,
-                };
+                }
             }
         })
     }
@@ -112,7 +328,7 @@ impl<T: Clone> std::clone::Clone for State<T> {
         State {
             data: self.data.clone(),
             subscribers: self.subscribers.clone(),
-            id: self.id,
+            id: self.id.clone(),
         }
     }
 }
@@ -130,3 +346,77 @@ impl<T: Clone> DerefMut for State<T> {
         Rc::get_mut(&mut self.data).expect("error: multiple references")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame<R>(build: impl FnOnce() -> R) -> R {
+        STATE_MANAGER.with(|manager| manager.borrow_mut().begin_frame());
+        let result = build();
+        STATE_MANAGER.with(|manager| manager.borrow_mut().end_frame());
+        result
+    }
+
+    fn set<T: Clone + 'static>(state: &State<T>, value: T) {
+        callback(state, move |data| *data = value.clone())();
+    }
+
+    #[test]
+    fn conditional_state_keeps_identity_of_others() {
+        let build = |show: bool| {
+            let optional = show.then(|| State::new(0));
+            let other = State::new(String::from("initial"));
+            (optional, other)
+        };
+
+        let (_, other) = frame(|| build(true));
+        set(&other, String::from("changed"));
+
+        let (optional, other) = frame(|| build(false));
+        assert!(optional.is_none());
+        assert_eq!(other.value(), "changed");
+    }
+
+    #[test]
+    fn scoped_state_follows_keys() {
+        let build = |keys: &[&str]| -> Vec<State<usize>> {
+            keys.iter()
+                .map(|key| scope(key, || State::new(0)))
+                .collect()
+        };
+
+        let states = frame(|| build(&["a", "b", "c"]));
+        set(&states[0], 1);
+        set(&states[2], 3);
+
+        let states = frame(|| build(&["c", "new", "a"]));
+        let values: Vec<usize> = states.iter().map(|state| state.value()).collect();
+        assert_eq!(values, vec![3, 0, 1]);
+    }
+
+    #[test]
+    fn state_of_removed_views_is_dropped() {
+        let build = |show: bool| show.then(|| State::new(0));
+
+        let state = frame(|| build(true)).unwrap();
+        set(&state, 5);
+        assert_eq!(frame(|| build(true)).unwrap().value(), 5);
+
+        frame(|| build(false));
+        assert_eq!(frame(|| build(true)).unwrap().value(), 0);
+    }
+
+    #[test]
+    fn state_of_different_types_does_not_collide() {
+        fn make<T: Clone + 'static>(value: T) -> State<T> {
+            State::new(value)
+        }
+
+        let number = frame(|| make(0));
+        set(&number, 7);
+
+        let text = frame(|| make(String::from("text")));
+        assert_eq!(text.value(), "text");
+    }
+}
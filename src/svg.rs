@@ -0,0 +1,267 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use vello::kurbo;
+use vello::peniko::{self, Color};
+
+use crate::image::Key;
+
+// Parsed SVG document, cheap to clone. Supports paths in groups with
+// transforms and opacity, filled and stroked with colors and gradients.
+// Text, embedded images, patterns, clip paths, masks and filters are skipped.
+#[derive(Clone)]
+pub struct SvgHandle {
+    tree: Rc<usvg::Tree>,
+}
+
+impl SvgHandle {
+    // Parses an SVG file once, later loads of the path are cached.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let key = Key::Path(path.as_ref().to_path_buf());
+        cached(key, || SvgHandle::parse(&std::fs::read(path.as_ref())?))
+    }
+
+    // Parses SVG data, e.g. from `include_bytes!`, cached by address.
+    pub fn from_static(bytes: &'static [u8]) -> anyhow::Result<Self> {
+        let key = Key::Static(bytes.as_ptr() as usize, bytes.len());
+        cached(key, || SvgHandle::parse(bytes))
+    }
+
+    // Parses SVG data, without caching.
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        let tree = usvg::Tree::from_data(bytes, &usvg::Options::default())?;
+        Ok(SvgHandle {
+            tree: Rc::new(tree),
+        })
+    }
+
+    // Size of the document in points, from its `width` and `height`.
+    pub fn size(&self) -> kurbo::Size {
+        let size = self.tree.size();
+        kurbo::Size::new(size.width() as f64, size.height() as f64)
+    }
+
+    // Draws the document from its origin, with all fills and strokes in the
+    // tint if any, keeping their opacity.
+    pub fn render(&self, scene: &mut vello::Scene, transform: kurbo::Affine, tint: Option<Color>) {
+        render_group(scene, transform, self.tree.root(), tint);
+    }
+}
+
+thread_local! {
+    static DOCUMENTS: RefCell<HashMap<Key, SvgHandle>> = RefCell::new(HashMap::new());
+}
+
+fn cached(
+    key: Key,
+    parse: impl FnOnce() -> anyhow::Result<SvgHandle>,
+) -> anyhow::Result<SvgHandle> {
+    if let Some(handle) = DOCUMENTS.with(|documents| documents.borrow().get(&key).cloned()) {
+        return Ok(handle);
+    }
+
+    let handle = parse()?;
+    DOCUMENTS.with(|documents| documents.borrow_mut().insert(key, handle.clone()));
+    Ok(handle)
+}
+
+fn render_group(
+    scene: &mut vello::Scene,
+    transform: kurbo::Affine,
+    group: &usvg::Group,
+    tint: Option<Color>,
+) {
+    // Transparent groups are composited as a whole, clipped to their bounds.
+    let layered = group.opacity().get() < 1.0;
+    if layered {
+        let bounds = group.abs_layer_bounding_box();
+        let bounds = kurbo::Rect::new(
+            bounds.left() as f64,
+            bounds.top() as f64,
+            bounds.right() as f64,
+            bounds.bottom() as f64,
+        );
+        scene.push_layer(
+            peniko::Mix::Normal,
+            group.opacity().get(),
+            transform,
+            &bounds,
+        );
+    }
+
+    for node in group.children() {
+        match node {
+            usvg::Node::Group(group) => render_group(scene, transform, group, tint),
+            usvg::Node::Path(path) if path.is_visible() => {
+                render_path(scene, transform, path, tint)
+            }
+            _ => {}
+        }
+    }
+
+    if layered {
+        scene.pop_layer();
+    }
+}
+
+fn render_path(
+    scene: &mut vello::Scene,
+    transform: kurbo::Affine,
+    path: &usvg::Path,
+    tint: Option<Color>,
+) {
+    let transform = transform * affine(path.abs_transform());
+    let shape = bez_path(path.data());
+
+    let fill = |scene: &mut vello::Scene| {
+        let Some(fill) = path.fill() else {
+            return;
+        };
+        let Some((brush, brush_transform)) = brush(fill.paint(), fill.opacity().get(), tint) else {
+            return;
+        };
+
+        let rule = match fill.rule() {
+            usvg::FillRule::NonZero => peniko::Fill::NonZero,
+            usvg::FillRule::EvenOdd => peniko::Fill::EvenOdd,
+        };
+        scene.fill(rule, transform, &brush, brush_transform, &shape);
+    };
+
+    let stroke = |scene: &mut vello::Scene| {
+        let Some(stroke) = path.stroke() else {
+            return;
+        };
+        let Some((brush, brush_transform)) = brush(stroke.paint(), stroke.opacity().get(), tint)
+        else {
+            return;
+        };
+
+        let style = kurbo::Stroke::new(stroke.width().get() as f64)
+            .with_join(match stroke.linejoin() {
+                usvg::LineJoin::Miter | usvg::LineJoin::MiterClip => kurbo::Join::Miter,
+                usvg::LineJoin::Round => kurbo::Join::Round,
+                usvg::LineJoin::Bevel => kurbo::Join::Bevel,
+            })
+            .with_miter_limit(stroke.miterlimit().get() as f64)
+            .with_caps(match stroke.linecap() {
+                usvg::LineCap::Butt => kurbo::Cap::Butt,
+                usvg::LineCap::Round => kurbo::Cap::Round,
+                usvg::LineCap::Square => kurbo::Cap::Square,
+            })
+            .with_dashes(
+                stroke.dashoffset() as f64,
+                stroke
+                    .dasharray()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|dash| *dash as f64),
+            );
+        scene.stroke(&style, transform, &brush, brush_transform, &shape);
+    };
+
+    match path.paint_order() {
+        usvg::PaintOrder::FillAndStroke => {
+            fill(scene);
+            stroke(scene);
+        }
+        usvg::PaintOrder::StrokeAndFill => {
+            stroke(scene);
+            fill(scene);
+        }
+    }
+}
+
+// Brush and brush transform of the paint, `None` for patterns.
+fn brush(
+    paint: &usvg::Paint,
+    opacity: f32,
+    tint: Option<Color>,
+) -> Option<(peniko::Brush, Option<kurbo::Affine>)> {
+    if let Some(tint) = tint {
+        return Some((peniko::Brush::Solid(tint.with_alpha_factor(opacity)), None));
+    }
+
+    let stops = |base: &usvg::BaseGradient| -> Vec<peniko::ColorStop> {
+        base.stops()
+            .iter()
+            .map(|stop| {
+                let color = color(stop.color(), stop.opacity().get() * opacity);
+                peniko::ColorStop::from((stop.offset().get(), color))
+            })
+            .collect()
+    };
+
+    let extend = |base: &usvg::BaseGradient| match base.spread_method() {
+        usvg::SpreadMethod::Pad => peniko::Extend::Pad,
+        usvg::SpreadMethod::Reflect => peniko::Extend::Reflect,
+        usvg::SpreadMethod::Repeat => peniko::Extend::Repeat,
+    };
+
+    match paint {
+        usvg::Paint::Color(value) => Some((peniko::Brush::Solid(color(*value, opacity)), None)),
+        usvg::Paint::LinearGradient(gradient) => {
+            let brush = peniko::Gradient::new_linear(
+                (gradient.x1() as f64, gradient.y1() as f64),
+                (gradient.x2() as f64, gradient.y2() as f64),
+            )
+            .with_extend(extend(gradient))
+            .with_stops(stops(gradient).as_slice());
+            Some((brush.into(), Some(affine(gradient.transform()))))
+        }
+        usvg::Paint::RadialGradient(gradient) => {
+            let brush = peniko::Gradient::new_two_point_radial(
+                (gradient.fx() as f64, gradient.fy() as f64),
+                0.0,
+                (gradient.cx() as f64, gradient.cy() as f64),
+                gradient.r().get(),
+            )
+            .with_extend(extend(gradient))
+            .with_stops(stops(gradient).as_slice());
+            Some((brush.into(), Some(affine(gradient.transform()))))
+        }
+        usvg::Paint::Pattern(_) => None,
+    }
+}
+
+fn color(color: usvg::Color, opacity: f32) -> Color {
+    Color::rgba8(
+        color.red,
+        color.green,
+        color.blue,
+        (opacity.clamp(0.0, 1.0) * 255.0).round() as u8,
+    )
+}
+
+fn affine(transform: usvg::Transform) -> kurbo::Affine {
+    kurbo::Affine::new([
+        transform.sx as f64,
+        transform.ky as f64,
+        transform.kx as f64,
+        transform.sy as f64,
+        transform.tx as f64,
+        transform.ty as f64,
+    ])
+}
+
+fn bez_path(path: &usvg::tiny_skia_path::Path) -> kurbo::BezPath {
+    use usvg::tiny_skia_path::PathSegment;
+
+    let point =
+        |point: usvg::tiny_skia_path::Point| kurbo::Point::new(point.x as f64, point.y as f64);
+    let mut bez_path = kurbo::BezPath::new();
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(to) => bez_path.move_to(point(to)),
+            PathSegment::LineTo(to) => bez_path.line_to(point(to)),
+            PathSegment::QuadTo(control, to) => bez_path.quad_to(point(control), point(to)),
+            PathSegment::CubicTo(first, second, to) => {
+                bez_path.curve_to(point(first), point(second), point(to))
+            }
+            PathSegment::Close => bez_path.close_path(),
+        }
+    }
+    bez_path
+}
@@ -0,0 +1,421 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use vello::glyph::Glyph;
+use vello::{kurbo, peniko};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const THIN: FontWeight = FontWeight(100);
+    pub const LIGHT: FontWeight = FontWeight(300);
+    pub const NORMAL: FontWeight = FontWeight(400);
+    pub const MEDIUM: FontWeight = FontWeight(500);
+    pub const SEMIBOLD: FontWeight = FontWeight(600);
+    pub const BOLD: FontWeight = FontWeight(700);
+    pub const BLACK: FontWeight = FontWeight(900);
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        FontWeight::NORMAL
+    }
+}
+
+// Fonts bundled for tests, so that snapshots don't depend on the fonts
+// installed on the machine.
+#[cfg(test)]
+const TEST_FONTS: [&[u8]; 2] = [
+    include_bytes!("../snapshots/fonts/DejaVuSans.ttf"),
+    include_bytes!("../snapshots/fonts/DejaVuSans-Bold.ttf"),
+];
+
+// Number of shaped layouts kept around, the cache is cleared when it grows
+// beyond.
+const LAYOUT_CACHE_LIMIT: usize = 1024;
+
+#[derive(PartialEq, Eq, Hash)]
+struct LayoutKey {
+    text: String,
+    family: Option<String>,
+    size: u64,
+    weight: FontWeight,
+    max_width: Option<u64>,
+}
+
+pub struct FontManager {
+    database: fontdb::Database,
+    fonts: HashMap<fontdb::ID, peniko::Font>,
+    layouts: HashMap<LayoutKey, Rc<TextLayout>>,
+    shape_cx: swash::shape::ShapeContext,
+}
+
+impl FontManager {
+    fn new() -> Self {
+        let mut manager = FontManager {
+            database: fontdb::Database::new(),
+            fonts: HashMap::new(),
+            layouts: HashMap::new(),
+            shape_cx: swash::shape::ShapeContext::new(),
+        };
+
+        #[cfg(not(test))]
+        manager.database.load_system_fonts();
+
+        #[cfg(test)]
+        {
+            manager.database.set_sans_serif_family("DejaVu Sans");
+            for data in TEST_FONTS {
+                manager.register_font(data.to_vec());
+            }
+        }
+
+        manager
+    }
+
+    pub fn register_font(&mut self, data: Vec<u8>) {
+        self.database.load_font_data(data);
+        // Text may resolve to the new font.
+        self.layouts.clear();
+    }
+
+    // Resolves the closest match for the family and weight, falling back to the
+    // generic sans-serif family and then to any installed font.
+    pub fn font(&mut self, family: Option<&str>, weight: FontWeight) -> Option<peniko::Font> {
+        let query = |families: &[fontdb::Family]| {
+            self.database.query(&fontdb::Query {
+                families,
+                weight: fontdb::Weight(weight.0),
+                ..Default::default()
+            })
+        };
+
+        let id = family
+            .and_then(|family| query(&[fontdb::Family::Name(family)]))
+            .or_else(|| query(&[fontdb::Family::SansSerif]))
+            .or_else(|| query(&["DejaVu Sans", "Helvetica", "Arial"].map(fontdb::Family::Name)))
+            .or_else(|| self.database.faces().next().map(|face| face.id))?;
+
+        if let Some(font) = self.fonts.get(&id) {
+            return Some(font.clone());
+        }
+
+        let font = self.database.with_face_data(id, |data, index| {
+            peniko::Font::new(peniko::Blob::new(Arc::new(data.to_vec())), index)
+        })?;
+
+        self.fonts.insert(id, font.clone());
+        Some(font)
+    }
+}
+
+thread_local! {
+    pub static FONT_MANAGER: RefCell<FontManager> = RefCell::new(FontManager::new());
+}
+
+// Makes fonts from memory, e.g. bundled with `include_bytes!`, available by
+// their family name.
+pub fn register_font(data: Vec<u8>) {
+    FONT_MANAGER.with(|manager| manager.borrow_mut().register_font(data));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub family: Option<String>,
+    pub size: f64,
+    pub weight: FontWeight,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            family: None,
+            size: 16.0,
+            weight: FontWeight::NORMAL,
+        }
+    }
+}
+
+pub struct Line {
+    // Byte range of the line in the laid out string.
+    pub range: std::ops::Range<usize>,
+    // Glyph positions relative to the start of the baseline.
+    pub glyphs: Vec<Glyph>,
+    // Byte offsets of cluster boundaries with their x position, including the
+    // end of the line.
+    pub carets: Vec<(usize, f64)>,
+    pub baseline: f64,
+    pub width: f64,
+}
+
+pub struct TextLayout {
+    pub font: peniko::Font,
+    pub font_size: f64,
+    pub lines: Vec<Line>,
+    pub size: kurbo::Size,
+}
+
+impl TextLayout {
+    pub fn line_height(&self) -> f64 {
+        self.size.height / self.lines.len().max(1) as f64
+    }
+
+    // Position of the caret before the byte offset, relative to the layout.
+    pub fn caret_position(&self, offset: usize) -> kurbo::Point {
+        let line_height = self.line_height();
+
+        for (idx, line) in self.lines.iter().enumerate() {
+            let last = idx + 1 == self.lines.len();
+            if offset <= line.range.end || last {
+                let x = line
+                    .carets
+                    .iter()
+                    .rev()
+                    .find(|(start, _)| *start <= offset)
+                    .map_or(0.0, |(_, x)| *x);
+                return kurbo::Point::new(x, idx as f64 * line_height);
+            }
+        }
+
+        kurbo::Point::ZERO
+    }
+
+    // Byte offset of the caret closest to the point, relative to the layout.
+    pub fn offset_at(&self, point: kurbo::Point) -> usize {
+        let idx = (point.y / self.line_height()).floor().max(0.0) as usize;
+        let Some(line) = self.lines.get(idx.min(self.lines.len().saturating_sub(1))) else {
+            return 0;
+        };
+
+        line.carets
+            .iter()
+            .min_by(|(_, a), (_, b)| (a - point.x).abs().total_cmp(&(b - point.x).abs()))
+            .map_or(line.range.start, |(offset, _)| *offset)
+    }
+}
+
+struct Cluster {
+    range: std::ops::Range<usize>,
+    glyphs: Vec<Glyph>,
+    advance: f64,
+    whitespace: bool,
+}
+
+// Shapes the text and breaks it into lines at newlines and, if `max_width` is
+// given, greedily at whitespace so that lines fit. Words wider than
+// `max_width` overflow on a line of their own. Layouts are cached by text,
+// style and width, views are measured several times per frame.
+pub fn layout(text: &str, style: &TextStyle, max_width: Option<f64>) -> Option<Rc<TextLayout>> {
+    FONT_MANAGER.with(|manager| {
+        let mut manager = manager.borrow_mut();
+        let key = LayoutKey {
+            text: text.to_string(),
+            family: style.family.clone(),
+            size: style.size.to_bits(),
+            weight: style.weight,
+            max_width: max_width.map(f64::to_bits),
+        };
+
+        if let Some(layout) = manager.layouts.get(&key) {
+            return Some(layout.clone());
+        }
+
+        let layout = Rc::new(shape(&mut manager, text, style, max_width)?);
+        if manager.layouts.len() >= LAYOUT_CACHE_LIMIT {
+            manager.layouts.clear();
+        }
+        manager.layouts.insert(key, layout.clone());
+        Some(layout)
+    })
+}
+
+fn shape(
+    manager: &mut FontManager,
+    text: &str,
+    style: &TextStyle,
+    max_width: Option<f64>,
+) -> Option<TextLayout> {
+    let font = manager.font(style.family.as_deref(), style.weight)?;
+    let font_ref = swash::FontRef::from_index(font.data.as_ref(), font.index as usize)?;
+
+    let metrics = font_ref.metrics(&[]).scale(style.size as f32);
+    let ascent = metrics.ascent as f64;
+    let line_height = (metrics.ascent + metrics.descent + metrics.leading) as f64;
+
+    let mut lines = vec![];
+    let mut offset = 0;
+
+    for paragraph in text.split('\n') {
+        let mut shaper = manager
+            .shape_cx
+            .builder(font_ref)
+            .size(style.size as f32)
+            .build();
+
+        shaper.add_str(paragraph);
+
+        let mut clusters = vec![];
+        shaper.shape_with(|cluster| {
+            let range = cluster.source.to_range();
+            let mut advance = 0.0;
+            let glyphs = cluster
+                .glyphs
+                .iter()
+                .map(|glyph| {
+                    let positioned = Glyph {
+                        id: glyph.id as u32,
+                        x: advance as f32 + glyph.x,
+                        y: -glyph.y,
+                    };
+                    advance += glyph.advance as f64;
+                    positioned
+                })
+                .collect();
+
+            clusters.push(Cluster {
+                whitespace: paragraph[range.clone()].chars().all(char::is_whitespace),
+                range: offset + range.start..offset + range.end,
+                glyphs,
+                advance,
+            });
+        });
+
+        for line in break_lines(&clusters, max_width) {
+            let mut x = 0.0;
+            let mut glyphs = vec![];
+            let mut carets = vec![];
+
+            for cluster in line {
+                glyphs.extend(cluster.glyphs.iter().map(|glyph| Glyph {
+                    x: glyph.x + x as f32,
+                    ..*glyph
+                }));
+                carets.push((cluster.range.start, x));
+                x += cluster.advance;
+            }
+
+            carets.push((line.last().map_or(offset, |c| c.range.end), x));
+
+            let trailing: f64 = line
+                .iter()
+                .rev()
+                .take_while(|cluster| cluster.whitespace)
+                .map(|cluster| cluster.advance)
+                .sum();
+
+            lines.push(Line {
+                range: line.first().map_or(offset, |c| c.range.start)
+                    ..line.last().map_or(offset, |c| c.range.end),
+                glyphs,
+                carets,
+                baseline: lines.len() as f64 * line_height + ascent,
+                width: x - trailing,
+            });
+        }
+
+        offset += paragraph.len() + 1;
+    }
+
+    let width = lines.iter().map(|line| line.width).fold(0.0, f64::max);
+
+    Some(TextLayout {
+        font,
+        font_size: style.size,
+        size: kurbo::Size::new(width, lines.len() as f64 * line_height),
+        lines,
+    })
+}
+
+fn break_lines(clusters: &[Cluster], max_width: Option<f64>) -> Vec<&[Cluster]> {
+    let Some(max_width) = max_width else {
+        return vec![clusters];
+    };
+
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut width = 0.0;
+    let mut idx = 0;
+
+    while idx < clusters.len() {
+        // A word with its trailing whitespace, only the word itself has to fit.
+        let word_end = (idx..clusters.len())
+            .find(|&i| clusters[i].whitespace)
+            .unwrap_or(clusters.len());
+        let space_end = (word_end..clusters.len())
+            .find(|&i| !clusters[i].whitespace)
+            .unwrap_or(clusters.len());
+
+        let word: f64 = clusters[idx..word_end].iter().map(|c| c.advance).sum();
+        let space: f64 = clusters[word_end..space_end]
+            .iter()
+            .map(|c| c.advance)
+            .sum();
+
+        if idx > start && width + word > max_width {
+            lines.push(&clusters[start..idx]);
+            start = idx;
+            width = 0.0;
+        }
+
+        width += word + space;
+        idx = space_end;
+    }
+
+    lines.push(&clusters[start..]);
+    lines
+}
+
+pub fn draw(
+    layout: &TextLayout,
+    origin: kurbo::Point,
+    color: peniko::Color,
+    transform: kurbo::Affine,
+    scene: &mut vello::Scene,
+) {
+    for line in &layout.lines {
+        scene
+            .draw_glyphs(&layout.font)
+            .font_size(layout.font_size as f32)
+            .transform(transform * kurbo::Affine::translate((origin.x, origin.y + line.baseline)))
+            .brush(color)
+            .draw(peniko::Fill::NonZero, line.glyphs.iter().copied());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_only_see_bundled_fonts() {
+        FONT_MANAGER.with(|manager| {
+            let manager = manager.borrow();
+            let families: Vec<&str> = manager
+                .database
+                .faces()
+                .map(|face| face.families[0].0.as_str())
+                .collect();
+            assert_eq!(families, ["DejaVu Sans", "DejaVu Sans"]);
+        });
+    }
+
+    #[test]
+    fn layouts_are_cached_by_text_style_and_width() {
+        let style = TextStyle::default();
+        let layout_of = |text, style: &TextStyle, width| layout(text, style, width).unwrap();
+
+        let hello = layout_of("Hello", &style, None);
+        assert!(Rc::ptr_eq(&hello, &layout_of("Hello", &style, None)));
+        assert!(!Rc::ptr_eq(&hello, &layout_of("Hello", &style, Some(10.0))));
+        assert!(!Rc::ptr_eq(&hello, &layout_of("Hallo", &style, None)));
+
+        let bold = TextStyle {
+            weight: FontWeight::BOLD,
+            ..style
+        };
+        assert!(!Rc::ptr_eq(&hello, &layout_of("Hello", &bold, None)));
+    }
+}
@@ -0,0 +1,274 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use vello::kurbo;
+
+use crate::animation;
+use crate::core::{self, AnyView, Presentation};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+// How a view is presented while it's inserted into or removed from a stack,
+// applied on top of its animated properties. Takes effect when the insertion
+// or removal is animated, e.g. within `with_animation`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transition {
+    Identity,
+    Opacity,
+    // Scales from the factor, around the view's scale anchor.
+    Scale(f64),
+    // Moves from the offset in points.
+    Offset(kurbo::Vec2),
+    // Moves from beyond the edge of the view's own frame.
+    Move(Edge),
+    Combined(Box<Transition>, Box<Transition>),
+    Asymmetric {
+        insertion: Box<Transition>,
+        removal: Box<Transition>,
+    },
+}
+
+impl Transition {
+    // Inserts from the left and removes to the right.
+    pub fn slide() -> Self {
+        Transition::asymmetric(Transition::Move(Edge::Left), Transition::Move(Edge::Right))
+    }
+
+    pub fn asymmetric(insertion: Transition, removal: Transition) -> Self {
+        Transition::Asymmetric {
+            insertion: Box::new(insertion),
+            removal: Box::new(removal),
+        }
+    }
+
+    pub fn combined(self, other: Transition) -> Self {
+        Transition::Combined(Box::new(self), Box::new(other))
+    }
+
+    // Applies the transition to the presented properties of a view of the
+    // size in points, from `amount` 0 for the identity to 1 for the view
+    // being fully inserted or removed.
+    pub fn apply(
+        &self,
+        presentation: Presentation,
+        amount: f64,
+        size: kurbo::Size,
+        removal: bool,
+    ) -> Presentation {
+        match self {
+            Transition::Identity => presentation,
+            Transition::Opacity => Presentation {
+                opacity: presentation.opacity * (1.0 - amount) as f32,
+                ..presentation
+            },
+            Transition::Scale(scale) => Presentation {
+                scale: presentation.scale * (1.0 + (scale - 1.0) * amount),
+                ..presentation
+            },
+            Transition::Offset(offset) => Presentation {
+                offset: presentation.offset + *offset * amount,
+                ..presentation
+            },
+            Transition::Move(edge) => {
+                let offset = match edge {
+                    Edge::Top => kurbo::Vec2::new(0.0, -size.height),
+                    Edge::Bottom => kurbo::Vec2::new(0.0, size.height),
+                    Edge::Left => kurbo::Vec2::new(-size.width, 0.0),
+                    Edge::Right => kurbo::Vec2::new(size.width, 0.0),
+                };
+                Transition::Offset(offset).apply(presentation, amount, size, removal)
+            }
+            Transition::Combined(first, second) => {
+                let presentation = first.apply(presentation, amount, size, removal);
+                second.apply(presentation, amount, size, removal)
+            }
+            Transition::Asymmetric {
+                insertion,
+                removal: transition,
+            } => match removal {
+                false => insertion.apply(presentation, amount, size, removal),
+                true => transition.apply(presentation, amount, size, removal),
+            },
+        }
+    }
+}
+
+// Applies the insertion transition of a view being placed. The view starts out
+// fully transitioned if it wasn't laid out in the previous frame.
+pub fn insert<V: core::BaseFields + ?Sized>(view: &V, presentation: Presentation) -> Presentation {
+    let base = view.base();
+    let Some(transition) = &base.transition else {
+        return presentation;
+    };
+
+    let amount = animation::animate_from(base.id.get(), "transition", 1.0, 0.0);
+    transition.apply(presentation, amount, base.size.get(), false)
+}
+
+// A removed view drawn until its removal transition finished.
+struct Leaving {
+    view: Rc<dyn AnyView>,
+    presentation: Presentation,
+}
+
+#[derive(Default)]
+pub struct Transitions {
+    // Children with a transition from the previous view tree, by identity.
+    retired: HashMap<u64, Rc<dyn AnyView>>,
+    // Identities of the children each stack laid out in the previous frame.
+    children: HashMap<u64, (Vec<u64>, bool)>,
+    leaving: HashMap<u64, Vec<Leaving>>,
+}
+
+thread_local! {
+    static TRANSITIONS: RefCell<Transitions> = RefCell::new(Transitions::default());
+}
+
+// Keeps the laid out children with a transition of a dropped stack, until the
+// stack of the next view tree found whether they were removed.
+pub fn retire(elements: &mut Vec<Box<dyn AnyView>>) {
+    let retired: Vec<_> = elements
+        .drain(..)
+        .filter(|element| element.base().transition.is_some() && element.base().id.get() != 0)
+        .collect();
+
+    if retired.is_empty() {
+        return;
+    }
+
+    TRANSITIONS.with(|transitions| {
+        let mut transitions = transitions.borrow_mut();
+        for element in retired {
+            transitions
+                .retired
+                .insert(element.base().id.get(), Rc::from(element));
+        }
+    });
+}
+
+// Compares the children a stack laid out with those of the previous frame, and
+// starts the removal transition of the missing ones.
+pub fn update(parent: u64, children: Vec<u64>) {
+    let stale = TRANSITIONS.with(|transitions| {
+        let transitions = &mut *transitions.borrow_mut();
+        let (previous, _) = transitions
+            .children
+            .insert(parent, (children.clone(), true))
+            .unwrap_or_default();
+
+        let leaving = transitions.leaving.entry(parent).or_default();
+        let (mut stale, remaining): (Vec<_>, Vec<_>) = std::mem::take(leaving)
+            .into_iter()
+            .partition(|leaving| children.contains(&leaving.view.id()));
+        *leaving = remaining;
+
+        for id in previous.into_iter().filter(|id| !children.contains(id)) {
+            if let Some(view) = transitions.retired.remove(&id) {
+                leaving.push(Leaving {
+                    presentation: view.base().presentation.get(),
+                    view,
+                });
+            }
+        }
+
+        stale.extend(
+            children
+                .iter()
+                .filter_map(|id| transitions.retired.remove(id))
+                .map(|view| Leaving {
+                    presentation: view.base().presentation.get(),
+                    view,
+                }),
+        );
+        stale
+    });
+
+    // Dropping views may retire their own children.
+    drop(stale);
+}
+
+// Draws the removed children of a stack with their removal transition, and
+// drops those that finished.
+pub fn draw_removed(parent: u64, cx: core::Context, scene: &mut vello::Scene) {
+    let leaving: Vec<_> = TRANSITIONS.with(|transitions| {
+        let transitions = transitions.borrow();
+        transitions.leaving.get(&parent).map_or(vec![], |leaving| {
+            leaving
+                .iter()
+                .map(|leaving| (leaving.view.clone(), leaving.presentation))
+                .collect()
+        })
+    });
+
+    let mut finished = vec![];
+    for (view, presentation) in leaving {
+        let base = view.base();
+        let id = base.id.get();
+
+        animation::inherit(id, parent);
+        let amount = animation::animate(id, "transition", 1.0);
+        if amount >= 1.0 {
+            finished.push(id);
+            continue;
+        }
+
+        let transition = base.transition.as_ref().unwrap_or(&Transition::Identity);
+        let size = base.size.get();
+        base.presentation
+            .set(transition.apply(presentation, amount, size, true));
+        core::draw(view.as_ref(), cx, scene);
+    }
+
+    let finished: Vec<_> = TRANSITIONS.with(|transitions| {
+        let mut transitions = transitions.borrow_mut();
+        let Some(leaving) = transitions.leaving.get_mut(&parent) else {
+            return vec![];
+        };
+        let (finished, remaining) = std::mem::take(leaving)
+            .into_iter()
+            .partition(|leaving| finished.contains(&leaving.view.id()));
+        *leaving = remaining;
+        finished
+    });
+
+    drop(finished);
+}
+
+// Number of removed children of a stack that are still transitioning.
+pub fn removing(parent: u64) -> usize {
+    TRANSITIONS.with(|transitions| {
+        transitions
+            .borrow()
+            .leaving
+            .get(&parent)
+            .map_or(0, |leaving| leaving.len())
+    })
+}
+
+// Drops the retired children that are not being removed with a transition,
+// and forgets stacks that were not laid out.
+pub fn end_frame() {
+    let stale = TRANSITIONS.with(|transitions| {
+        let transitions = &mut *transitions.borrow_mut();
+        transitions
+            .children
+            .retain(|_, (_, live)| std::mem::take(live));
+
+        let children = &transitions.children;
+        let (removed, remaining): (HashMap<_, _>, _) = std::mem::take(&mut transitions.leaving)
+            .into_iter()
+            .partition(|(parent, leaving)| !children.contains_key(parent) || leaving.is_empty());
+        transitions.leaving = remaining;
+
+        (std::mem::take(&mut transitions.retired), removed)
+    });
+
+    drop(stale);
+}
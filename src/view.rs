@@ -1,41 +1,146 @@
-use crate::{core, *};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Instant;
+
+use winit::event::TouchPhase;
+
+use crate::core::{self, AnyView, Axis, BaseFields};
+use crate::editor::{self, Editor};
+use crate::image;
+use crate::paint::{self, Paint, StrokeStyle};
+use crate::text::{self, FontWeight, TextStyle};
+use crate::AnyView;
+use crate::{animation, event, state, svg, transition};
+use vello::kurbo::Shape as _;
+use vello::peniko::Color;
 use vello::{kurbo, peniko};
 
 #[derive(AnyView)]
+#[view(container)]
 pub struct Loop {
     view_base: core::Base,
     elements: Vec<Box<dyn core::AnyView>>,
 }
 
 impl Loop {
+    #[track_caller]
     pub fn new<F, T>(range: std::ops::Range<u32>, func: F) -> Loop
     where
         T: AnyView,
         F: Fn(u32) -> T,
     {
+        let container = state::container_id();
+        // Built before the loop itself like the children of any other view,
+        // see `state::resolve`.
+        let elements = range
+            .map(|idx| state::scope((container, idx), || Box::new(func(idx)) as Box<dyn AnyView>))
+            .collect();
+
         Loop {
             view_base: core::Base::default(),
-            elements: range
-                .map(|idx| Box::new(func(idx)) as Box<dyn AnyView>)
-                .collect(),
+            elements,
         }
     }
 }
 
+impl Stack for Loop {
+    fn elements(&self) -> &[Box<dyn core::AnyView>] {
+        &self.elements
+    }
+}
+
+// Loops are flattened into the containers holding them.
+impl core::Layout for Loop {}
+
 impl core::Draw for Loop {
     fn draw(&self, _: core::Context, _: &mut vello::Scene) {}
 }
 
+// Builds a view for each item of a collection, identifying the views and
+// their state by the extracted key instead of their position, so that items
+// keep their state when the collection is reordered, grown or shrunk.
+//
+//     ForEach::new(rows, |row| row.id, |row| Text::new(&row.title))
+#[derive(AnyView)]
+#[view(container)]
+pub struct ForEach {
+    view_base: core::Base,
+    elements: Vec<Box<dyn core::AnyView>>,
+}
+
+impl ForEach {
+    #[track_caller]
+    pub fn new<I, K, T, G, F>(items: I, key: G, func: F) -> ForEach
+    where
+        I: IntoIterator,
+        K: std::hash::Hash,
+        T: AnyView,
+        G: Fn(&I::Item) -> K,
+        F: Fn(I::Item) -> T,
+    {
+        let container = state::container_id();
+        let elements = items
+            .into_iter()
+            .map(|item| {
+                // Keys only need to be unique within this `ForEach`.
+                let key = state::hash(&(container, key(&item)));
+                let mut element = state::scope(key, || Box::new(func(item)) as Box<dyn AnyView>);
+                element.base_mut().key = Some(key);
+                element
+            })
+            .collect();
+
+        ForEach {
+            view_base: core::Base::default(),
+            elements,
+        }
+    }
+}
+
+impl Stack for ForEach {
+    fn elements(&self) -> &[Box<dyn core::AnyView>] {
+        &self.elements
+    }
+}
+
+impl core::Layout for ForEach {}
+
+impl core::Draw for ForEach {
+    fn draw(&self, _: core::Context, _: &mut vello::Scene) {}
+}
+
+// Keeps children with a transition around for their removal, see
+// `transition::retire`.
+macro_rules! impl_retire {
+    ($($view:ident),+) => {
+        $(
+            impl Drop for $view {
+                fn drop(&mut self) {
+                    transition::retire(&mut self.elements);
+                }
+            }
+        )+
+    };
+}
+
+impl_retire!(Loop, ForEach, VStack, HStack, ZStack, Grid);
+
 pub trait Stack: BaseFields {
     fn elements(&self) -> &[Box<dyn core::AnyView>];
 
     // Given that the root view is a container and always drawn,
     // only view containers need to check for element visibility.
-    fn recurse_stack(&self, mut operation: impl FnMut(&Box<dyn AnyView>)) {
-        fn r(element: &Box<dyn AnyView>, operation: &mut impl FnMut(&Box<dyn AnyView>)) {
-            if let Some(list) = element.as_any().downcast_ref::<Loop>() {
-                for element in list.elements.iter().filter(|e| e.visible()) {
-                    r(element, operation);
+    fn recurse_stack<'a>(&'a self, mut operation: impl FnMut(&'a dyn AnyView)) {
+        fn r<'a>(element: &'a dyn AnyView, operation: &mut impl FnMut(&'a dyn AnyView)) {
+            let any = element.as_any();
+            let list = match any.downcast_ref::<Loop>() {
+                Some(list) => Some(&list.elements),
+                None => any.downcast_ref::<ForEach>().map(|list| &list.elements),
+            };
+
+            if let Some(list) = list {
+                for element in list.iter().filter(|e| e.visible()) {
+                    r(element.as_ref(), operation);
                 }
             } else {
                 operation(element);
@@ -43,15 +148,303 @@ pub trait Stack: BaseFields {
         }
 
         for element in self.elements().iter().filter(|e| e.visible()) {
-            r(element, &mut operation);
+            r(element.as_ref(), &mut operation);
+        }
+    }
+
+    fn children(&self) -> Vec<&dyn AnyView> {
+        let mut children = vec![];
+        self.recurse_stack(|element| children.push(element));
+        children
+    }
+}
+
+impl Axis {
+    fn main(self, size: kurbo::Size) -> f64 {
+        match self {
+            Axis::Horizontal => size.width,
+            Axis::Vertical => size.height,
+        }
+    }
+
+    fn cross(self, size: kurbo::Size) -> f64 {
+        match self {
+            Axis::Horizontal => size.height,
+            Axis::Vertical => size.width,
+        }
+    }
+
+    fn proposal(self, main: Option<f64>, cross: Option<f64>) -> core::Proposal {
+        match self {
+            Axis::Horizontal => core::Proposal {
+                width: main,
+                height: cross,
+                axis: Some(self),
+            },
+            Axis::Vertical => core::Proposal {
+                width: cross,
+                height: main,
+                axis: Some(self),
+            },
+        }
+    }
+
+    fn size(self, main: f64, cross: f64) -> kurbo::Size {
+        match self {
+            Axis::Horizontal => kurbo::Size::new(main, cross),
+            Axis::Vertical => kurbo::Size::new(cross, main),
+        }
+    }
+}
+
+// Offers the available space along the stack axis to the children in order of
+// decreasing layout priority and then increasing flexibility, so that children
+// which can't grow take what they need and flexible children share what
+// remains. Space for the minimum sizes of lower priority children is reserved.
+// Returns the proposal and the chosen size for each child.
+fn distribute(
+    children: &[&dyn AnyView],
+    axis: Axis,
+    proposal: core::Proposal,
+    spacing: f64,
+) -> Vec<(core::Proposal, kurbo::Size)> {
+    let (main, cross) = match axis {
+        Axis::Horizontal => (proposal.width, proposal.height),
+        Axis::Vertical => (proposal.height, proposal.width),
+    };
+
+    let Some(main) = main else {
+        return children
+            .iter()
+            .map(|child| {
+                let proposal = axis.proposal(None, cross);
+                (proposal, core::measure(*child, proposal))
+            })
+            .collect();
+    };
+
+    // Priority, minimum size and flexibility of each child.
+    let measure = |child: &&dyn AnyView| {
+        let min = axis.main(core::measure(*child, axis.proposal(Some(0.0), cross)));
+        let max = axis.main(core::measure(
+            *child,
+            axis.proposal(Some(f64::INFINITY), cross),
+        ));
+        (child.base().layout_priority, min, max - min)
+    };
+
+    let measured: Vec<_> = children.iter().map(measure).collect();
+    let mut order: Vec<usize> = (0..children.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (measured[a], measured[b]);
+        b.0.total_cmp(&a.0).then(a.2.total_cmp(&b.2))
+    });
+
+    let spacing = spacing * children.len().saturating_sub(1) as f64;
+    let mut remaining = f64::max(main - spacing, 0.0);
+    let mut reserved: f64 = measured.iter().map(|(_, min, _)| min).sum();
+    let mut result = vec![(core::Proposal::UNSPECIFIED, kurbo::Size::ZERO); children.len()];
+
+    for group in order.chunk_by(|&a, &b| measured[a].0 == measured[b].0) {
+        reserved -= group.iter().map(|&idx| measured[idx].1).sum::<f64>();
+        let mut available = f64::max(remaining - reserved, 0.0);
+
+        for (count, &idx) in group.iter().enumerate() {
+            let share = available / (group.len() - count) as f64;
+            let proposal = axis.proposal(Some(share), cross);
+            let size = core::measure(children[idx], proposal);
+            available = f64::max(available - axis.main(size), 0.0);
+            remaining = f64::max(remaining - axis.main(size), 0.0);
+            result[idx] = (proposal, size);
         }
     }
+
+    result
+}
+
+// Offsets of the children along the guide's axis which line up their guides,
+// within content of the length. The aligned children as a whole are placed
+// within any excess length, e.g. of a flexible frame, as the guide aligns.
+fn align(
+    children: &[&dyn AnyView],
+    sizes: &[(core::Proposal, kurbo::Size)],
+    guide: core::Guide,
+    length: Option<f64>,
+) -> (Vec<f64>, f64) {
+    let guides: Vec<f64> = children
+        .iter()
+        .zip(sizes)
+        .map(|(child, (proposal, size))| core::guide(*child, guide, *proposal, *size))
+        .collect();
+
+    let line = guides.iter().copied().fold(0.0, f64::max);
+    let extent = guides
+        .iter()
+        .zip(sizes)
+        .map(|(value, (_, size))| line - value + guide.length(*size))
+        .fold(0.0, f64::max);
+
+    let excess = length.map_or(0.0, |length| f64::max(length - extent, 0.0)) * guide.fraction();
+    let offsets = guides.iter().map(|value| excess + line - value).collect();
+    (offsets, extent)
+}
+
+fn stack_size(
+    sizes: &[(core::Proposal, kurbo::Size)],
+    axis: Axis,
+    spacing: f64,
+    cross: f64,
+) -> kurbo::Size {
+    let main = sizes.iter().map(|(_, size)| axis.main(*size)).sum::<f64>()
+        + spacing * sizes.len().saturating_sub(1) as f64;
+
+    axis.size(main, cross)
+}
+
+// Size of the content of a view chosen for the proposal, without padding.
+fn content_size<V: core::Layout>(view: &V, proposal: core::Proposal) -> kurbo::Size {
+    let size = core::measure(view, proposal);
+    kurbo::Size::new(
+        f64::max(size.width - view.padding_horizontal(), 0.0),
+        f64::max(size.height - view.padding_vertical(), 0.0),
+    )
+}
+
+fn measure_stack<S: Stack + core::Layout>(
+    stack: &S,
+    proposal: core::Proposal,
+    axis: Axis,
+    spacing: f64,
+    guide: core::Guide,
+) -> kurbo::Size {
+    let content = core::content_proposal(stack, proposal);
+    let children = stack.children();
+    let sizes = distribute(&children, axis, content, spacing);
+    let (_, cross) = align(&children, &sizes, guide, None);
+    core::resolve_size(stack, proposal, stack_size(&sizes, axis, spacing, cross))
+}
+
+// Baseline of the first child of a stack, or the baseline of its first line
+// of text for a stack of text.
+fn stack_baseline<S: Stack + core::Layout>(
+    stack: &S,
+    proposal: core::Proposal,
+    axis: Axis,
+    spacing: f64,
+    guide: core::Guide,
+) -> Option<f64> {
+    let children = stack.children();
+    let first = children.first()?;
+
+    let content = core::content_proposal(stack, proposal);
+    let sizes = distribute(&children, axis, content, spacing);
+    let (proposal_0, size_0) = sizes[0];
+    let baseline = core::guide(
+        *first,
+        core::Guide::Vertical(core::VerticalAlignment::FirstBaseline),
+        proposal_0,
+        size_0,
+    );
+
+    let offset = match axis {
+        Axis::Vertical => 0.0,
+        Axis::Horizontal => {
+            let height = content_size(stack, proposal).height;
+            align(&children, &sizes, guide, Some(height)).0[0]
+        }
+    };
+
+    Some(stack.padding_top() + offset + baseline)
+}
+
+fn layout_stack<S: Stack + core::Layout>(
+    stack: &S,
+    cx: core::Context,
+    proposal: core::Proposal,
+    axis: Axis,
+    spacing: f64,
+    guide: core::Guide,
+) {
+    let cx = core::place(stack, cx, core::measure(stack, proposal));
+
+    let children = stack.children();
+    let content = core::content_proposal(stack, proposal);
+    let origin = stack.origin();
+
+    let sizes = distribute(&children, axis, content, spacing);
+    let cross = axis.cross(content_size(stack, proposal));
+    let (offsets, _) = align(&children, &sizes, guide, Some(cross));
+    let mut main = 0.0;
+
+    for (index, ((child, (proposal, size)), offset)) in
+        children.iter().zip(sizes).zip(offsets).enumerate()
+    {
+        let id = core::child_id(stack.id(), *child, index);
+        let location = origin + axis.size(main, offset).to_vec2();
+        child.layout(core::Context { location, id, ..cx }, proposal);
+
+        // Offset the origin for the next element in the stack.
+        main += axis.main(size) + spacing;
+    }
+
+    transition::update(
+        stack.id(),
+        children.iter().map(|child| child.id()).collect(),
+    );
+}
+
+// Flexible space along the axis of the enclosing stack, pushing its siblings
+// apart. Outside of stacks it fills the proposed space in both directions.
+#[derive(Default, AnyView)]
+#[view(layout)]
+pub struct Spacer {
+    view_base: core::Base,
+    min_length: f64,
+}
+
+impl Spacer {
+    pub fn min_length(mut self, length: f64) -> Self {
+        self.min_length = length;
+        self
+    }
+}
+
+impl core::Layout for Spacer {
+    fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+        let content = core::content_proposal(self, proposal);
+        let min = self.min_length * core::ui_scale();
+        let length = |proposed: Option<f64>| proposed.unwrap_or(0.0).max(min);
+
+        let size = match proposal.axis {
+            Some(axis) => {
+                let main = match axis {
+                    Axis::Horizontal => content.width,
+                    Axis::Vertical => content.height,
+                };
+                axis.size(length(main), 0.0)
+            }
+            None => kurbo::Size::new(length(content.width), length(content.height)),
+        };
+
+        core::resolve_size(self, proposal, size)
+    }
+}
+
+impl core::Draw for Spacer {
+    fn draw(&self, _: core::Context, _: &mut vello::Scene) {}
+
+    // Empty space isn't hit.
+    fn contains(&self, _point: kurbo::Point) -> bool {
+        false
+    }
 }
 
 #[derive(AnyView)]
+#[view(container)]
 pub struct VStack {
     view_base: core::Base,
     spacing: f64,
+    alignment: core::HorizontalAlignment,
     elements: Vec<Box<dyn core::AnyView>>,
 }
 
@@ -60,6 +453,7 @@ impl VStack {
         VStack {
             view_base: core::Base::default(),
             elements: elements.into_view_sequence(),
+            alignment: core::HorizontalAlignment::default(),
             spacing: 0.0,
         }
     }
@@ -68,6 +462,11 @@ impl VStack {
         self.spacing = distance;
         self
     }
+
+    pub fn alignment(mut self, alignment: core::HorizontalAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
 }
 
 impl Stack for VStack {
@@ -77,63 +476,49 @@ impl Stack for VStack {
 }
 
 impl core::Layout for VStack {
-    fn layout(&self, mut cx: core::Context) {
-        let mut width = 0.0;
-        let mut height = 0.0;
-        let mut count = 0.0;
-
-        self.view_base.origin.set(vello::kurbo::Point {
-            x: cx.location.x + self.padding_left(),
-            y: cx.location.y + self.padding_top(),
-        });
-
-        let process = |element: &Box<dyn AnyView>| {
-            element.layout(core::Context {
-                // Apply the origin offset of the VStack itself.
-                location: kurbo::Point {
-                    x: cx.location.x + self.padding_left(),
-                    y: cx.location.y + self.padding_top(),
-                },
-                ..cx
-            });
-
-            // Offset origin.y for the next element in the VStack.
-            let spacing = self.spacing * core::ui_scale();
-            cx.location.y += element.height() + element.padding_vertical() + spacing;
-            height += element.height() + element.padding_vertical();
-            width = f64::max(width, element.width() + element.padding_horizontal());
-            count += 1.0;
-        };
-
-        self.recurse_stack(process);
+    fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+        let spacing = self.spacing * core::ui_scale();
+        measure_stack(
+            self,
+            proposal,
+            Axis::Vertical,
+            spacing,
+            self.alignment.into(),
+        )
+    }
 
-        if self.width() == 0.0 {
-            self.view_base.size.set(vello::kurbo::Size {
-                width: width / core::ui_scale(),
-                height: self.view_base.size.get().height,
-            });
-        }
+    fn layout(&self, cx: core::Context, proposal: core::Proposal) {
+        let spacing = self.spacing * core::ui_scale();
+        layout_stack(
+            self,
+            cx,
+            proposal,
+            Axis::Vertical,
+            spacing,
+            self.alignment.into(),
+        );
+    }
 
-        if self.height() == 0.0 {
-            self.view_base.size.set(vello::kurbo::Size {
-                width: self.view_base.size.get().width,
-                height: height / core::ui_scale() + f64::max(count - 1.0, 0.0) * self.spacing,
-            });
-        }
+    fn first_baseline(&self, proposal: core::Proposal) -> Option<f64> {
+        let spacing = self.spacing * core::ui_scale();
+        stack_baseline(
+            self,
+            proposal,
+            Axis::Vertical,
+            spacing,
+            self.alignment.into(),
+        )
     }
 }
 
 impl core::Draw for VStack {
     fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
-        println!(
-            "L{} VStack size: {} origin: {}",
-            cx.level,
-            self.size(),
-            self.origin()
-        );
+        // Removed children are drawn below the remaining ones.
+        transition::draw_removed(self.id(), cx, scene);
 
-        self.recurse_stack(|element: &Box<dyn AnyView>| {
-            element.draw(
+        self.recurse_stack(|element: &dyn AnyView| {
+            core::draw(
+                element,
                 core::Context {
                     level: cx.level + 1,
                     ..cx
@@ -145,9 +530,11 @@ impl core::Draw for VStack {
 }
 
 #[derive(AnyView)]
+#[view(container)]
 pub struct HStack {
     view_base: core::Base,
     spacing: f64,
+    alignment: core::VerticalAlignment,
     elements: Vec<Box<dyn core::AnyView>>,
 }
 
@@ -156,6 +543,7 @@ impl HStack {
         HStack {
             view_base: core::Base::default(),
             spacing: 0.0,
+            alignment: core::VerticalAlignment::default(),
             elements: elements.into_view_sequence(),
         }
     }
@@ -164,6 +552,11 @@ impl HStack {
         self.spacing = distance;
         self
     }
+
+    pub fn alignment(mut self, alignment: core::VerticalAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
 }
 
 impl Stack for HStack {
@@ -173,63 +566,48 @@ impl Stack for HStack {
 }
 
 impl core::Layout for HStack {
-    fn layout(&self, mut cx: core::Context) {
-        let mut width = 0.0;
-        let mut height = 0.0;
-        let mut count = 0.0;
-
-        self.view_base.origin.set(vello::kurbo::Point {
-            x: cx.location.x + self.padding_left(),
-            y: cx.location.y + self.padding_top(),
-        });
-
-        let process = |element: &Box<dyn AnyView>| {
-            element.layout(core::Context {
-                // Apply the origin offset of the HStack itself.
-                location: kurbo::Point {
-                    x: cx.location.x + self.padding_left(),
-                    y: cx.location.y + self.padding_top(),
-                },
-                ..cx
-            });
-
-            // Offset origin.x for the next element in the HStack.
-            let spacing = self.spacing * core::ui_scale();
-            cx.location.x += element.width() + element.padding_horizontal() + spacing;
-            width += element.width() + element.padding_horizontal();
-            height = f64::max(height, element.height() + element.padding_vertical());
-            count += 1.0;
-        };
-
-        self.recurse_stack(process);
+    fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+        let spacing = self.spacing * core::ui_scale();
+        measure_stack(
+            self,
+            proposal,
+            Axis::Horizontal,
+            spacing,
+            self.alignment.into(),
+        )
+    }
 
-        if self.width() == 0.0 {
-            self.view_base.size.set(vello::kurbo::Size {
-                width: width / core::ui_scale() + f64::max(count - 1.0, 0.0) * self.spacing,
-                height: self.view_base.size.get().height,
-            });
-        }
+    fn layout(&self, cx: core::Context, proposal: core::Proposal) {
+        let spacing = self.spacing * core::ui_scale();
+        layout_stack(
+            self,
+            cx,
+            proposal,
+            Axis::Horizontal,
+            spacing,
+            self.alignment.into(),
+        );
+    }
 
-        if self.height() == 0.0 {
-            self.view_base.size.set(vello::kurbo::Size {
-                width: self.view_base.size.get().width,
-                height: height / core::ui_scale(),
-            });
-        }
+    fn first_baseline(&self, proposal: core::Proposal) -> Option<f64> {
+        let spacing = self.spacing * core::ui_scale();
+        stack_baseline(
+            self,
+            proposal,
+            Axis::Horizontal,
+            spacing,
+            self.alignment.into(),
+        )
     }
 }
 
 impl core::Draw for HStack {
     fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
-        println!(
-            "L{} HStack size: {} origin: {}",
-            cx.level,
-            self.size(),
-            self.origin()
-        );
+        transition::draw_removed(self.id(), cx, scene);
 
-        self.recurse_stack(|element: &Box<dyn AnyView>| {
-            element.draw(
+        self.recurse_stack(|element: &dyn AnyView| {
+            core::draw(
+                element,
                 core::Context {
                     level: cx.level + 1,
                     ..cx
@@ -241,8 +619,10 @@ impl core::Draw for HStack {
 }
 
 #[derive(AnyView)]
+#[view(container)]
 pub struct ZStack {
     view_base: core::Base,
+    alignment: core::Alignment,
     elements: Vec<Box<dyn core::AnyView>>,
 }
 
@@ -250,9 +630,38 @@ impl ZStack {
     pub fn new<T: core::ViewSequence>(elements: T) -> ZStack {
         ZStack {
             view_base: core::Base::default(),
+            alignment: core::Alignment::default(),
             elements: elements.into_view_sequence(),
         }
     }
+
+    pub fn alignment(mut self, alignment: core::Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    // Offsets of the children from the stack's content origin, and the size
+    // of the aligned children, within content of the size if known.
+    fn align(
+        &self,
+        proposal: core::Proposal,
+        size: Option<kurbo::Size>,
+    ) -> (Vec<kurbo::Vec2>, kurbo::Size) {
+        let children = self.children();
+        let content = core::content_proposal(self, proposal);
+        let sizes: Vec<_> = children
+            .iter()
+            .map(|child| (content, core::measure(*child, content)))
+            .collect();
+
+        let horizontal = core::Guide::Horizontal(self.alignment.horizontal);
+        let vertical = core::Guide::Vertical(self.alignment.vertical);
+        let (xs, width) = align(&children, &sizes, horizontal, size.map(|size| size.width));
+        let (ys, height) = align(&children, &sizes, vertical, size.map(|size| size.height));
+
+        let offsets = xs.into_iter().zip(ys).map(kurbo::Vec2::from).collect();
+        (offsets, kurbo::Size::new(width, height))
+    }
 }
 
 impl Stack for ZStack {
@@ -261,59 +670,58 @@ impl Stack for ZStack {
     }
 }
 
+// Children of a ZStack are all offered the full space of the stack, and
+// aligned with each other.
 impl core::Layout for ZStack {
-    fn layout(&self, cx: Context) {
-        let mut width = 0.0;
-        let mut height = 0.0;
+    fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+        let (_, size) = self.align(proposal, None);
+        core::resolve_size(self, proposal, size)
+    }
 
-        self.view_base.origin.set(vello::kurbo::Point {
-            x: cx.location.x + self.padding_left(),
-            y: cx.location.y + self.padding_top(),
-        });
+    fn layout(&self, cx: core::Context, proposal: core::Proposal) {
+        let cx = core::place(self, cx, core::measure(self, proposal));
 
-        let process = |element: &Box<dyn AnyView>| {
-            element.layout(core::Context {
-                // Apply the origin offset of the ZStack itself.
-                location: kurbo::Point {
-                    x: cx.location.x + self.padding_left(),
-                    y: cx.location.y + self.padding_top(),
+        let content = core::content_proposal(self, proposal);
+        let (offsets, _) = self.align(proposal, Some(content_size(self, proposal)));
+        for (index, (child, offset)) in self.children().into_iter().zip(offsets).enumerate() {
+            child.layout(
+                core::Context {
+                    location: self.origin() + offset,
+                    id: core::child_id(self.id(), child, index),
+                    ..cx
                 },
-                ..cx
-            });
+                content,
+            );
+        }
 
-            width = f64::max(width, element.width() + element.padding_horizontal());
-            height = f64::max(height, element.height() + element.padding_vertical());
-        };
+        let children = self.children().iter().map(|child| child.id()).collect();
+        transition::update(self.id(), children);
+    }
 
-        self.recurse_stack(process);
+    fn first_baseline(&self, proposal: core::Proposal) -> Option<f64> {
+        let children = self.children();
+        let first = children.first()?;
 
-        if self.width() == 0.0 {
-            self.view_base.size.set(vello::kurbo::Size {
-                width: width / core::ui_scale(),
-                height: self.view_base.size.get().width,
-            });
-        }
+        let content = core::content_proposal(self, proposal);
+        let (offsets, _) = self.align(proposal, Some(content_size(self, proposal)));
+        let baseline = core::guide(
+            *first,
+            core::Guide::Vertical(core::VerticalAlignment::FirstBaseline),
+            content,
+            core::measure(*first, content),
+        );
 
-        if self.height() == 0.0 {
-            self.view_base.size.set(vello::kurbo::Size {
-                width: self.view_base.size.get().width,
-                height: height / core::ui_scale(),
-            });
-        }
+        Some(self.padding_top() + offsets[0].y + baseline)
     }
 }
 
 impl core::Draw for ZStack {
     fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
-        println!(
-            "L{} ZStack size: {} origin: {}",
-            cx.level,
-            self.size(),
-            self.origin()
-        );
+        transition::draw_removed(self.id(), cx, scene);
 
-        self.recurse_stack(|element: &Box<dyn AnyView>| {
-            element.draw(
+        self.recurse_stack(|element: &dyn AnyView| {
+            core::draw(
+                element,
                 core::Context {
                     level: cx.level + 1,
                     ..cx
@@ -324,124 +732,3801 @@ impl core::Draw for ZStack {
     }
 }
 
-enum Style {
-    Fill(Color),
-    Stroke(Color, f64),
+// Size of a column or row of a grid, lengths are in points.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GridTrack {
+    Fixed(f64),
+    // Sized to fit the cells in it.
+    Fit,
+    // Shares the space left by the other tracks, within the bounds. Without a
+    // proposal it fits the cells in it.
+    Flexible { min: f64, max: f64 },
+    // Repeated as often as tracks of the minimum length fit the proposed
+    // space, sharing it as flexible tracks. A single track without a proposal.
+    Adaptive { min: f64, max: f64 },
 }
 
-impl Default for Style {
-    fn default() -> Self {
-        Style::Stroke(Color::rgb8(1, 1, 1), 1.0)
+impl GridTrack {
+    pub fn flexible() -> Self {
+        GridTrack::Flexible {
+            min: 0.0,
+            max: f64::INFINITY,
+        }
+    }
+
+    pub fn adaptive(min: f64) -> Self {
+        GridTrack::Adaptive {
+            min,
+            max: f64::INFINITY,
+        }
     }
 }
 
-#[derive(Default, AnyView)]
-pub struct Rectangle {
-    view_base: core::Base,
-    fill: Option<peniko::Color>,
-    stroke: Option<(peniko::Color, f64)>,
+// Tracks of a grid with adaptive ones repeated, lengths are in pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Track {
+    Fixed(f64),
+    Fit,
+    Flexible(f64, f64),
 }
 
-impl Rectangle {
-    pub fn fill(mut self, color: Color) -> Self {
-        self.fill = Some(color);
-        self
+// Repeats adaptive tracks as often as they fit the space left by the others.
+fn expand_tracks(tracks: &[GridTrack], available: Option<f64>, spacing: f64) -> Vec<Track> {
+    let scale = core::ui_scale();
+    let adaptive = tracks
+        .iter()
+        .filter(|track| matches!(track, GridTrack::Adaptive { .. }))
+        .count();
+    let others: f64 = tracks
+        .iter()
+        .map(|track| match *track {
+            GridTrack::Fixed(length) => length * scale + spacing,
+            GridTrack::Flexible { min, .. } => min * scale + spacing,
+            _ => 0.0,
+        })
+        .sum();
+
+    let mut expanded = vec![];
+    for track in tracks {
+        match *track {
+            GridTrack::Fixed(length) => expanded.push(Track::Fixed(length * scale)),
+            GridTrack::Fit => expanded.push(Track::Fit),
+            GridTrack::Flexible { min, max } => {
+                expanded.push(Track::Flexible(min * scale, max * scale))
+            }
+            GridTrack::Adaptive { min, max } => {
+                let (min, max) = (min * scale, max * scale);
+                let count = match available {
+                    Some(available) if available.is_finite() => {
+                        let space = f64::max(available - others, 0.0) / adaptive as f64;
+                        ((space + spacing) / f64::max(min + spacing, 1.0)).floor() as usize
+                    }
+                    _ => 1,
+                };
+                expanded.extend(std::iter::repeat_n(Track::Flexible(min, max), count.max(1)));
+            }
+        }
     }
 
-    pub fn stroke(mut self, color: Color, stroke_width: f64) -> Self {
-        self.stroke = Some((color, stroke_width));
-        self
+    expanded
+}
+
+// Places the cells row by row into the first free area spanning their
+// columns and rows. Returns the columns and rows spanned by each cell.
+fn place_cells(
+    children: &[&dyn AnyView],
+    columns: usize,
+) -> Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+    let mut occupied: Vec<Vec<bool>> = vec![];
+    let (mut row, mut column) = (0, 0);
+
+    let free = |occupied: &Vec<Vec<bool>>, row: usize, column: usize, span: (usize, usize)| {
+        (row..row + span.1).all(|row| {
+            occupied
+                .get(row)
+                .is_none_or(|cells| !cells[column..column + span.0].contains(&true))
+        })
+    };
+
+    children
+        .iter()
+        .map(|child| {
+            let (width, height) = child.base().grid_span;
+            let span = (width.clamp(1, columns), height.max(1));
+
+            while column + span.0 > columns || !free(&occupied, row, column, span) {
+                column += 1;
+                if column + span.0 > columns {
+                    row += 1;
+                    column = 0;
+                }
+            }
+
+            if occupied.len() < row + span.1 {
+                occupied.resize(row + span.1, vec![false; columns]);
+            }
+            for cells in &mut occupied[row..row + span.1] {
+                cells[column..column + span.0].fill(true);
+            }
+
+            let cell = (column..column + span.0, row..row + span.1);
+            column += span.0;
+            cell
+        })
+        .collect()
+}
+
+// Length each track needs to fit its cells, given the tracks each cell spans
+// and its length. Cells spanning several tracks grow the last of them.
+fn track_content(
+    count: usize,
+    cells: impl Iterator<Item = (std::ops::Range<usize>, f64)> + Clone,
+    spacing: f64,
+) -> Vec<f64> {
+    let mut content = vec![0.0; count];
+    for (span, length) in cells.clone().filter(|(span, _)| span.len() == 1) {
+        content[span.start] = f64::max(content[span.start], length);
+    }
+
+    for (span, length) in cells.filter(|(span, _)| span.len() > 1) {
+        let spanned = track_extent(&content[span.clone()], spacing);
+        content[span.end - 1] += f64::max(length - spanned, 0.0);
     }
+
+    content
 }
 
-impl core::Draw for Rectangle {
-    fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
-        println!("L{} Rectangle {} {}", cx.level, self.size(), self.origin());
+// Lengths of the tracks within the available space. Flexible tracks share
+// what the others leave in order of increasing maximum length.
+fn resolve_tracks(
+    tracks: &[Track],
+    available: Option<f64>,
+    spacing: f64,
+    content: &[f64],
+) -> Vec<f64> {
+    let mut lengths: Vec<f64> = tracks
+        .iter()
+        .zip(content)
+        .map(|(track, content)| match (*track, available) {
+            (Track::Fixed(length), _) => length,
+            (Track::Fit, _) => *content,
+            (Track::Flexible(min, max), None) => content.max(min).min(max.max(min)),
+            (Track::Flexible(..), Some(_)) => 0.0,
+        })
+        .collect();
 
-        let rect = self.rect();
+    let Some(available) = available else {
+        return lengths;
+    };
 
-        if let Some(color) = self.fill {
-            scene.fill(
-                peniko::Fill::NonZero,
-                kurbo::Affine::IDENTITY,
-                color,
-                None,
-                &rect,
-            );
-        }
+    let mut flexible: Vec<usize> = (0..tracks.len())
+        .filter(|&idx| matches!(tracks[idx], Track::Flexible(..)))
+        .collect();
+    flexible.sort_by(|&a, &b| match (tracks[a], tracks[b]) {
+        (Track::Flexible(_, a), Track::Flexible(_, b)) => a.total_cmp(&b),
+        _ => std::cmp::Ordering::Equal,
+    });
 
-        if let Some((color, stroke_width)) = self.stroke {
-            scene.stroke(
-                &kurbo::Stroke::new(stroke_width).with_join(kurbo::Join::Miter),
-                kurbo::Affine::IDENTITY,
-                color,
-                None,
-                &rect,
-            );
+    let mut remaining = f64::max(available - track_extent(&lengths, spacing), 0.0);
+    for (count, &idx) in flexible.iter().enumerate() {
+        if let Track::Flexible(min, max) = tracks[idx] {
+            let share = remaining / (flexible.len() - count) as f64;
+            lengths[idx] = share.max(min).min(max.max(min));
+            remaining = f64::max(remaining - lengths[idx], 0.0);
         }
     }
+
+    lengths
 }
 
-#[derive(Default, AnyView)]
-pub struct Circle {
-    view_base: core::Base,
-    fill: Option<peniko::Color>,
-    stroke: Option<(peniko::Color, f64)>,
+fn track_extent(lengths: &[f64], spacing: f64) -> f64 {
+    lengths.iter().sum::<f64>() + spacing * lengths.len().saturating_sub(1) as f64
 }
 
-impl Circle {
-    pub fn diameter(self, diameter: f64) -> Self {
-        self.size(diameter, diameter)
-    }
+// Tracks of a grid and the tracks spanned by each cell, laid out for a
+// proposal, in pixels.
+struct GridLayout {
+    columns: Vec<f64>,
+    rows: Vec<f64>,
+    column_spacing: f64,
+    row_spacing: f64,
+    cells: Vec<(std::ops::Range<usize>, std::ops::Range<usize>)>,
+}
 
-    pub fn radius(self, radius: f64) -> Self {
-        self.size(radius * 2.0, radius * 2.0)
+impl GridLayout {
+    fn size(&self) -> kurbo::Size {
+        kurbo::Size::new(
+            track_extent(&self.columns, self.column_spacing),
+            track_extent(&self.rows, self.row_spacing),
+        )
     }
 
-    pub fn fill(mut self, color: Color) -> Self {
-        self.fill = Some(color);
-        self
-    }
+    // Area of the cell relative to the origin of the grid's content.
+    fn area(
+        &self,
+        (columns, rows): &(std::ops::Range<usize>, std::ops::Range<usize>),
+    ) -> kurbo::Rect {
+        let start = |lengths: &[f64], spacing: f64, idx: usize| {
+            lengths[..idx].iter().sum::<f64>() + spacing * idx as f64
+        };
 
-    pub fn stroke(mut self, color: Color, stroke_width: f64) -> Self {
-        self.stroke = Some((color, stroke_width));
-        self
+        kurbo::Rect::from_origin_size(
+            (
+                start(&self.columns, self.column_spacing, columns.start),
+                start(&self.rows, self.row_spacing, rows.start),
+            ),
+            (
+                track_extent(&self.columns[columns.clone()], self.column_spacing),
+                track_extent(&self.rows[rows.clone()], self.row_spacing),
+            ),
+        )
     }
 }
 
-impl core::Draw for Circle {
-    fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
-        println!(
-            "L{} Circle size: {} origin: {}",
-            cx.level,
-            self.size(),
-            self.origin()
-        );
+// Offsets of the cells within their areas along the guide's axis. Cells
+// spanning the same tracks with the same alignment are aligned with each
+// other, e.g. on their first baselines across a row.
+fn align_cells(
+    children: &[&dyn AnyView],
+    sizes: &[(core::Proposal, kurbo::Size)],
+    keys: &[(std::ops::Range<usize>, core::Guide)],
+    lengths: &[f64],
+) -> Vec<f64> {
+    let mut offsets = vec![None; children.len()];
 
-        let rect = self.rect();
-        let circle = vello::kurbo::Circle::new(
-            (rect.x0 + self.width() / 2.0, rect.y0 + self.height() / 2.0),
-            self.width() / 2.0,
+    for first in 0..children.len() {
+        if offsets[first].is_some() {
+            continue;
+        }
+
+        let group: Vec<usize> = (first..children.len())
+            .filter(|&idx| keys[idx] == keys[first])
+            .collect();
+        let (aligned, _) = align(
+            &group.iter().map(|&idx| children[idx]).collect::<Vec<_>>(),
+            &group.iter().map(|&idx| sizes[idx]).collect::<Vec<_>>(),
+            keys[first].1,
+            Some(lengths[first]),
         );
 
-        if let Some(color) = self.fill {
-            scene.fill(
-                peniko::Fill::NonZero,
-                kurbo::Affine::IDENTITY,
-                color,
-                None,
-                &circle,
-            );
+        for (idx, offset) in group.into_iter().zip(aligned) {
+            offsets[idx] = Some(offset);
         }
+    }
 
-        if let Some((color, stroke_width)) = self.stroke {
-            scene.stroke(
-                &kurbo::Stroke::new(stroke_width),
-                kurbo::Affine::IDENTITY,
-                color,
-                None,
-                &circle,
-            );
+    offsets.into_iter().map(Option::unwrap_or_default).collect()
+}
+
+// Lays out its children in cells of columns and rows, row by row. Rows
+// beyond the defined ones fit their cells, and without columns the grid has
+// a single flexible one.
+//
+//     Grid::new((...))
+//         .columns(vec![GridTrack::Fixed(80.0), GridTrack::flexible()])
+//         .spacing(10.0)
+#[derive(AnyView)]
+#[view(container)]
+pub struct Grid {
+    view_base: core::Base,
+    columns: Vec<GridTrack>,
+    rows: Vec<GridTrack>,
+    column_spacing: f64,
+    row_spacing: f64,
+    alignment: core::Alignment,
+    elements: Vec<Box<dyn core::AnyView>>,
+}
+
+impl Grid {
+    pub fn new<T: core::ViewSequence>(elements: T) -> Grid {
+        Grid {
+            view_base: core::Base::default(),
+            columns: vec![],
+            rows: vec![],
+            column_spacing: 0.0,
+            row_spacing: 0.0,
+            alignment: core::Alignment::default(),
+            elements: elements.into_view_sequence(),
         }
     }
+
+    pub fn columns(mut self, columns: impl Into<Vec<GridTrack>>) -> Self {
+        self.columns = columns.into();
+        self
+    }
+
+    pub fn rows(mut self, rows: impl Into<Vec<GridTrack>>) -> Self {
+        self.rows = rows.into();
+        self
+    }
+
+    pub fn spacing(mut self, distance: f64) -> Self {
+        self.column_spacing = distance;
+        self.row_spacing = distance;
+        self
+    }
+
+    pub fn column_spacing(mut self, distance: f64) -> Self {
+        self.column_spacing = distance;
+        self
+    }
+
+    pub fn row_spacing(mut self, distance: f64) -> Self {
+        self.row_spacing = distance;
+        self
+    }
+
+    // Alignment of the cells within their areas, unless set on a cell via
+    // `.grid_cell_alignment()`.
+    pub fn alignment(mut self, alignment: core::Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    fn measure(&self, children: &[&dyn AnyView], proposal: core::Proposal) -> GridLayout {
+        let column_spacing = self.column_spacing * core::ui_scale();
+        let row_spacing = self.row_spacing * core::ui_scale();
+        let content = core::content_proposal(self, proposal);
+
+        let mut columns = expand_tracks(&self.columns, content.width, column_spacing);
+        if columns.is_empty() {
+            columns.push(Track::Flexible(0.0, f64::INFINITY));
+        }
+
+        let cells = place_cells(children, columns.len());
+        let mut rows = expand_tracks(&self.rows, content.height, row_spacing);
+        let count = cells.iter().map(|(_, rows)| rows.end).max().unwrap_or(0);
+        if rows.len() < count {
+            rows.resize(count, Track::Fit);
+        }
+
+        let ideal = children.iter().zip(&cells).map(|(child, (columns, _))| {
+            let width = core::measure(*child, core::Proposal::UNSPECIFIED).width;
+            (columns.clone(), width)
+        });
+        let content_widths = track_content(columns.len(), ideal, column_spacing);
+        let widths = resolve_tracks(&columns, content.width, column_spacing, &content_widths);
+
+        let heights = children.iter().zip(&cells).map(|(child, (columns, rows))| {
+            let proposal = core::Proposal {
+                width: Some(track_extent(&widths[columns.clone()], column_spacing)),
+                height: None,
+                axis: None,
+            };
+            (rows.clone(), core::measure(*child, proposal).height)
+        });
+        let content_heights = track_content(rows.len(), heights, row_spacing);
+        let heights = resolve_tracks(&rows, content.height, row_spacing, &content_heights);
+
+        GridLayout {
+            columns: widths,
+            rows: heights,
+            column_spacing,
+            row_spacing,
+            cells,
+        }
+    }
+
+    // Proposal and offset from the grid's content origin of each child, with
+    // the tracks aligned within content of the size.
+    fn arrange(
+        &self,
+        children: &[&dyn AnyView],
+        grid: &GridLayout,
+        size: kurbo::Size,
+    ) -> Vec<(core::Proposal, kurbo::Vec2)> {
+        let areas: Vec<kurbo::Rect> = grid.cells.iter().map(|cell| grid.area(cell)).collect();
+        let sizes: Vec<(core::Proposal, kurbo::Size)> = children
+            .iter()
+            .zip(&areas)
+            .map(|(child, area)| {
+                let proposal = core::Proposal::from(area.size());
+                (proposal, core::measure(*child, proposal))
+            })
+            .collect();
+
+        let alignments: Vec<core::Alignment> = children
+            .iter()
+            .map(|child| child.base().grid_alignment.unwrap_or(self.alignment))
+            .collect();
+        let xs = align_cells(
+            children,
+            &sizes,
+            &grid
+                .cells
+                .iter()
+                .zip(&alignments)
+                .map(|((columns, _), alignment)| (columns.clone(), alignment.horizontal.into()))
+                .collect::<Vec<_>>(),
+            &areas.iter().map(|area| area.width()).collect::<Vec<_>>(),
+        );
+        let ys = align_cells(
+            children,
+            &sizes,
+            &grid
+                .cells
+                .iter()
+                .zip(&alignments)
+                .map(|((_, rows), alignment)| (rows.clone(), alignment.vertical.into()))
+                .collect::<Vec<_>>(),
+            &areas.iter().map(|area| area.height()).collect::<Vec<_>>(),
+        );
+
+        let excess = size - grid.size();
+        let origin = kurbo::Vec2::new(
+            f64::max(excess.width, 0.0)
+                * core::Guide::Horizontal(self.alignment.horizontal).fraction(),
+            f64::max(excess.height, 0.0)
+                * core::Guide::Vertical(self.alignment.vertical).fraction(),
+        );
+
+        sizes
+            .iter()
+            .zip(areas)
+            .zip(xs.into_iter().zip(ys))
+            .map(|(((proposal, _), area), offset)| {
+                (
+                    *proposal,
+                    origin + area.origin().to_vec2() + kurbo::Vec2::from(offset),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Stack for Grid {
+    fn elements(&self) -> &[Box<dyn core::AnyView>] {
+        &self.elements
+    }
+}
+
+impl core::Layout for Grid {
+    fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+        let grid = self.measure(&self.children(), proposal);
+        core::resolve_size(self, proposal, grid.size())
+    }
+
+    fn layout(&self, cx: core::Context, proposal: core::Proposal) {
+        let cx = core::place(self, cx, core::measure(self, proposal));
+
+        let children = self.children();
+        let grid = self.measure(&children, proposal);
+        let cells = self.arrange(&children, &grid, content_size(self, proposal));
+        for (index, (child, (proposal, offset))) in children.iter().zip(cells).enumerate() {
+            child.layout(
+                core::Context {
+                    location: self.origin() + offset,
+                    id: core::child_id(self.id(), *child, index),
+                    ..cx
+                },
+                proposal,
+            );
+        }
+
+        let children = children.iter().map(|child| child.id()).collect();
+        transition::update(self.id(), children);
+    }
+
+    fn first_baseline(&self, proposal: core::Proposal) -> Option<f64> {
+        let children = self.children();
+        let first = children.first()?;
+
+        let grid = self.measure(&children, proposal);
+        let cells = self.arrange(&children, &grid, content_size(self, proposal));
+        let (proposal, offset) = cells[0];
+        let baseline = core::guide(
+            *first,
+            core::Guide::Vertical(core::VerticalAlignment::FirstBaseline),
+            proposal,
+            core::measure(*first, proposal),
+        );
+
+        Some(self.padding_top() + offset.y + baseline)
+    }
+}
+
+impl core::Draw for Grid {
+    fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
+        transition::draw_removed(self.id(), cx, scene);
+
+        self.recurse_stack(|element: &dyn AnyView| {
+            core::draw(
+                element,
+                core::Context {
+                    level: cx.level + 1,
+                    ..cx
+                },
+                scene,
+            );
+        });
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScrollAxes {
+    Vertical,
+    Horizontal,
+    Both,
+}
+
+impl ScrollAxes {
+    fn horizontal(self) -> bool {
+        self != ScrollAxes::Vertical
+    }
+
+    fn vertical(self) -> bool {
+        self != ScrollAxes::Horizontal
+    }
+}
+
+// Scroll position kept across view tree rebuilds.
+#[derive(Clone, Default)]
+struct Scroll {
+    offset: kurbo::Vec2,
+    // Pixels per second, the content keeps gliding at a decaying velocity
+    // after a trackpad gesture ends.
+    velocity: kurbo::Vec2,
+    gliding: bool,
+    last: Option<Instant>,
+}
+
+// Sizes from the last layout, shared with the scroll handler.
+#[derive(Copy, Clone)]
+struct Extent {
+    content: kurbo::Size,
+    viewport: kurbo::Size,
+    axes: ScrollAxes,
+}
+
+impl Extent {
+    fn clamp(&self, offset: kurbo::Vec2) -> kurbo::Vec2 {
+        let max = self.content - self.viewport;
+        kurbo::Vec2 {
+            x: match self.axes.horizontal() {
+                true => offset.x.clamp(0.0, max.width.max(0.0)),
+                false => 0.0,
+            },
+            y: match self.axes.vertical() {
+                true => offset.y.clamp(0.0, max.height.max(0.0)),
+                false => 0.0,
+            },
+        }
+    }
+}
+
+// Shows its content clipped to the proposed size, offset by the scroll
+// position. The content is proposed an unbounded size along the scroll axes.
+#[derive(AnyView)]
+#[view(container)]
+pub struct ScrollView {
+    view_base: core::Base,
+    axes: ScrollAxes,
+    indicators: bool,
+    scroll: state::State<Scroll>,
+    extent: Rc<Cell<Extent>>,
+    elements: Vec<Box<dyn core::AnyView>>,
+}
+
+impl ScrollView {
+    #[track_caller]
+    pub fn new<T: core::ViewSequence>(elements: T) -> ScrollView {
+        let scroll = state::State::new(Scroll::default());
+        let extent = Rc::new(Cell::new(Extent {
+            content: kurbo::Size::ZERO,
+            viewport: kurbo::Size::ZERO,
+            axes: ScrollAxes::Vertical,
+        }));
+
+        let on_scroll: core::ScrollAction = Box::new({
+            let (scroll, extent) = (scroll.clone(), extent.clone());
+            move |event: &event::ScrollEvent| {
+                let extent = extent.get();
+                let mut next = scroll.value();
+                let now = Instant::now();
+
+                let current = extent.clamp(next.offset);
+                let offset = extent.clamp(current - event.delta);
+                let moved = offset != current;
+
+                if event.phase == TouchPhase::Ended {
+                    next.gliding = next.velocity != kurbo::Vec2::ZERO;
+                } else {
+                    let elapsed = next.last.map_or(0.0, |last| (now - last).as_secs_f64());
+                    next.velocity = match moved && elapsed > 0.0 && elapsed < 0.1 {
+                        true => (offset - current) / elapsed,
+                        false => kurbo::Vec2::ZERO,
+                    };
+                    next.gliding = false;
+                    next.offset = offset;
+                }
+
+                next.last = Some(now);
+
+                // Scrolling past the edge is left to enclosing scroll views.
+                if !moved && !next.gliding {
+                    return false;
+                }
+
+                state::callback(&scroll, move |scroll| *scroll = next.clone())();
+                true
+            }
+        });
+
+        ScrollView {
+            view_base: core::Base {
+                on_scroll: Some(on_scroll),
+                ..Default::default()
+            },
+            axes: ScrollAxes::Vertical,
+            indicators: true,
+            scroll,
+            extent,
+            elements: elements.into_view_sequence(),
+        }
+    }
+
+    pub fn axes(mut self, axes: ScrollAxes) -> Self {
+        self.axes = axes;
+        self
+    }
+
+    pub fn indicators(mut self, indicators: bool) -> Self {
+        self.indicators = indicators;
+        self
+    }
+
+    fn content_proposal(&self, proposal: core::Proposal) -> core::Proposal {
+        let content = core::content_proposal(self, proposal);
+        core::Proposal {
+            width: content.width.filter(|_| !self.axes.horizontal()),
+            height: content.height.filter(|_| !self.axes.vertical()),
+            axis: None,
+        }
+    }
+
+    fn content_size(&self, proposal: core::Proposal) -> kurbo::Size {
+        let proposal = self.content_proposal(proposal);
+        self.children()
+            .iter()
+            .map(|child| core::measure(*child, proposal))
+            .fold(kurbo::Size::ZERO, |a, b| {
+                kurbo::Size::new(f64::max(a.width, b.width), f64::max(a.height, b.height))
+            })
+    }
+
+    // Continues a trackpad gesture after it ended, slowing down over time.
+    // Stepped when laying out, so the content is placed at the new offset.
+    fn glide(&self) {
+        let mut scroll = self.scroll.value();
+        if !scroll.gliding {
+            return;
+        }
+
+        let now = Instant::now();
+        let elapsed = scroll
+            .last
+            .map_or(0.0, |last| (now - last).as_secs_f64())
+            .min(0.1);
+
+        let extent = self.extent.get();
+        let current = extent.clamp(scroll.offset);
+        scroll.offset = extent.clamp(current + scroll.velocity * elapsed);
+        scroll.velocity = match scroll.offset == current && elapsed > 0.0 {
+            true => kurbo::Vec2::ZERO,
+            false => scroll.velocity * (-4.0 * elapsed).exp(),
+        };
+        scroll.gliding = scroll.velocity.hypot() > 10.0;
+        scroll.last = Some(now);
+
+        state::callback(&self.scroll, move |value| *value = scroll.clone())();
+        event::request_frame();
+    }
+
+    fn draw_indicators(&self, cx: core::Context, scene: &mut vello::Scene) {
+        let rect = self.rect();
+        let extent = self.extent.get();
+        let offset = extent.clamp(self.scroll.value().offset);
+        let scale = core::ui_scale();
+        let (thickness, inset) = (4.0 * scale, 2.0 * scale);
+
+        // Position and length of the indicator along an axis.
+        let indicator = |viewport: f64, content: f64, offset: f64| {
+            let length = f64::max(viewport * viewport / content, 20.0 * scale).min(viewport);
+            let position = (viewport - length) * offset / (content - viewport);
+            (position, length)
+        };
+
+        let mut indicators = vec![];
+
+        if self.axes.vertical() && extent.content.height > extent.viewport.height {
+            let (y, length) = indicator(rect.height(), extent.content.height, offset.y);
+            let x = rect.x1 - inset - thickness;
+            indicators.push(kurbo::Rect::new(
+                x,
+                rect.y0 + y,
+                x + thickness,
+                rect.y0 + y + length,
+            ));
+        }
+
+        if self.axes.horizontal() && extent.content.width > extent.viewport.width {
+            let (x, length) = indicator(rect.width(), extent.content.width, offset.x);
+            let y = rect.y1 - inset - thickness;
+            indicators.push(kurbo::Rect::new(
+                rect.x0 + x,
+                y,
+                rect.x0 + x + length,
+                y + thickness,
+            ));
+        }
+
+        for indicator in indicators {
+            scene.fill(
+                peniko::Fill::NonZero,
+                cx.transform,
+                Color::rgba8(255, 255, 255, 120),
+                None,
+                &indicator.to_rounded_rect(thickness / 2.0),
+            );
+        }
+    }
+}
+
+impl Stack for ScrollView {
+    fn elements(&self) -> &[Box<dyn core::AnyView>] {
+        &self.elements
+    }
+}
+
+impl core::Layout for ScrollView {
+    fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+        let content = core::content_proposal(self, proposal);
+        let size = match (content.width, content.height) {
+            (Some(width), Some(height)) => kurbo::Size::new(width, height),
+            (width, height) => {
+                let fitting = self.content_size(proposal);
+                kurbo::Size::new(
+                    width.unwrap_or(fitting.width),
+                    height.unwrap_or(fitting.height),
+                )
+            }
+        };
+
+        core::resolve_size(self, proposal, size)
+    }
+
+    fn layout(&self, cx: core::Context, proposal: core::Proposal) {
+        let cx = core::place(self, cx, core::measure(self, proposal));
+
+        let rect = self.rect();
+        let extent = Extent {
+            content: self.content_size(proposal),
+            viewport: rect.size(),
+            axes: self.axes,
+        };
+        self.extent.set(extent);
+        self.glide();
+
+        let offset = extent.clamp(self.scroll.value().offset);
+        let clip = core::Clip::intersect(cx.clip, core::ClipShape::Rect, rect, cx.transform);
+        let content = self.content_proposal(proposal);
+
+        for (index, child) in self.children().into_iter().enumerate() {
+            child.layout(
+                core::Context {
+                    location: self.origin() - offset,
+                    id: core::child_id(self.id(), child, index),
+                    clip: Some(clip),
+                    ..cx
+                },
+                content,
+            );
+        }
+    }
+}
+
+impl core::Draw for ScrollView {
+    fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
+        let rect = self.rect();
+
+        scene.push_layer(peniko::Mix::Clip, 1.0, cx.transform, &rect);
+        self.recurse_stack(|element: &dyn AnyView| {
+            core::draw(
+                element,
+                core::Context {
+                    level: cx.level + 1,
+                    ..cx
+                },
+                scene,
+            );
+        });
+        scene.pop_layer();
+
+        if self.indicators {
+            self.draw_indicators(cx, scene);
+        }
+    }
+}
+
+// Fill and stroke of a shape view, drawn along the trimmed part of its path.
+#[derive(Clone)]
+pub struct ShapeStyle {
+    pub fill: Option<Paint>,
+    pub fill_rule: peniko::Fill,
+    pub stroke: Option<(Paint, StrokeStyle)>,
+    // Fractions of the path's length to draw from and to.
+    pub trim: (f64, f64),
+    // Trim last drawn while animating towards `trim`, which hit testing
+    // follows.
+    pub presented_trim: Cell<Option<(f64, f64)>>,
+}
+
+impl Default for ShapeStyle {
+    fn default() -> Self {
+        ShapeStyle {
+            fill: None,
+            fill_rule: peniko::Fill::NonZero,
+            stroke: None,
+            trim: (0.0, 1.0),
+            presented_trim: Cell::new(None),
+        }
+    }
+}
+
+// Shape views draw a path fitted to their rect, and are hit where they're
+// filled or stroked.
+pub trait Shape: BaseFields {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath;
+
+    // Open paths, e.g. lines, are only stroked.
+    fn closed(&self) -> bool {
+        true
+    }
+
+    // Whether strokes are aligned inside or outside of the path, rather than
+    // centered on it whatever their alignment.
+    fn aligns_strokes(&self) -> bool {
+        self.closed()
+    }
+
+    fn shape_style(&self) -> &ShapeStyle;
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle;
+
+    fn fill(mut self, paint: impl Into<Paint>) -> Self
+    where
+        Self: Sized,
+    {
+        self.shape_style_mut().fill = Some(paint.into());
+        self
+    }
+
+    fn fill_rule(mut self, rule: peniko::Fill) -> Self
+    where
+        Self: Sized,
+    {
+        self.shape_style_mut().fill_rule = rule;
+        self
+    }
+
+    fn stroke(mut self, paint: impl Into<Paint>, style: impl Into<StrokeStyle>) -> Self
+    where
+        Self: Sized,
+    {
+        self.shape_style_mut().stroke = Some((paint.into(), style.into()));
+        self
+    }
+
+    // Draws the part of the path between the fractions of its length, e.g.
+    // animating `to` from 0 to 1 draws the outline progressively.
+    fn trim(mut self, from: f64, to: f64) -> Self
+    where
+        Self: Sized,
+    {
+        self.shape_style_mut().trim = (from, to);
+        self
+    }
+}
+
+fn draw_shape<S: Shape>(shape: &S, cx: core::Context, scene: &mut vello::Scene) {
+    let rect = shape.rect();
+    let style = shape.shape_style();
+    let from = animation::animate(shape.id(), "trim_from", style.trim.0);
+    let to = animation::animate(shape.id(), "trim_to", style.trim.1);
+    style.presented_trim.set(Some((from, to)));
+    let path = |rect| trim_path(shape.path(rect), from, to);
+
+    if let Some(paint) = style.fill.as_ref().filter(|_| shape.closed()) {
+        let paint = animation::animate(shape.id(), "fill", paint.clone());
+        paint::fill(
+            scene,
+            style.fill_rule,
+            cx.transform,
+            &paint,
+            rect,
+            &path(rect),
+        );
+    }
+
+    if let Some((paint, style)) = &style.stroke {
+        let paint = animation::animate(shape.id(), "stroke", paint.clone());
+        let style = animation::animate(shape.id(), "stroke_style", style.clone());
+        let outline = shape.aligns_strokes().then(|| shape.path(rect));
+        paint::stroke(
+            scene,
+            &style,
+            cx.transform,
+            &paint,
+            rect,
+            &path(rect),
+            outline.as_ref(),
+        );
+    }
+}
+
+// Tolerance of path lengths, in pixels.
+const TRIM_ACCURACY: f64 = 0.01;
+
+// Part of the path between the fractions of its total length.
+fn trim_path(path: kurbo::BezPath, from: f64, to: f64) -> kurbo::BezPath {
+    use kurbo::{ParamCurve, ParamCurveArclen};
+
+    let (from, to) = (from.clamp(0.0, 1.0), to.clamp(0.0, 1.0));
+    if from <= 0.0 && to >= 1.0 {
+        return path;
+    }
+
+    let segments: Vec<_> = path
+        .segments()
+        .map(|segment| (segment, segment.arclen(TRIM_ACCURACY)))
+        .collect();
+    let total: f64 = segments.iter().map(|(_, length)| length).sum();
+    let (from, to) = (from * total, to * total);
+
+    let mut trimmed = kurbo::BezPath::new();
+    let mut start = 0.0;
+    let mut end = None;
+
+    for (segment, length) in segments {
+        let offset = start;
+        start += length;
+        if length <= 0.0 || start <= from || offset >= to {
+            continue;
+        }
+
+        let t0 = match from > offset {
+            true => segment.inv_arclen(from - offset, TRIM_ACCURACY),
+            false => 0.0,
+        };
+        let t1 = match to < start {
+            true => segment.inv_arclen(to - offset, TRIM_ACCURACY),
+            false => 1.0,
+        };
+
+        let part = segment.subsegment(t0..t1);
+        if end != Some(part.start()) {
+            trimmed.move_to(part.start());
+        }
+        trimmed.push(part.as_path_el());
+        end = Some(part.end());
+    }
+
+    trimmed
+}
+
+// Distance from a line within which it's hit, in points.
+const LINE_HIT_SLOP: f64 = 4.0;
+
+fn shape_contains<S: Shape>(shape: &S, point: kurbo::Point) -> bool {
+    let rect = shape.rect();
+    let style = shape.shape_style();
+    let (from, to) = style.presented_trim.get().unwrap_or(style.trim);
+    let path = |rect| trim_path(shape.path(rect), from, to);
+
+    if shape.closed() {
+        let winding = path(rect).winding(point);
+        let inside = match style.fill_rule {
+            peniko::Fill::NonZero => winding != 0,
+            peniko::Fill::EvenOdd => winding % 2 != 0,
+        };
+        if inside {
+            return true;
+        }
+    }
+
+    let Some((_, stroke_style)) = &style.stroke else {
+        return false;
+    };
+
+    let outline = shape.aligns_strokes().then(|| shape.path(rect));
+    let (mut stroke, clip) = paint::aligned_stroke(stroke_style, outline.as_ref());
+    if !shape.closed() {
+        stroke.width = stroke.width.max(LINE_HIT_SLOP * 2.0 * core::ui_scale());
+    }
+
+    kurbo::stroke(path(rect), &stroke, &kurbo::StrokeOpts::default(), 0.1).contains(point)
+        && clip.is_none_or(|clip| clip.contains(point))
+}
+
+macro_rules! impl_shape_draw {
+    ($($view:ident),+) => {
+        $(
+            impl core::Draw for $view {
+                fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
+                    draw_shape(self, cx, scene);
+                }
+
+                fn contains(&self, point: kurbo::Point) -> bool {
+                    shape_contains(self, point)
+                }
+            }
+        )+
+    };
+}
+
+impl_shape_draw!(
+    Path,
+    Rectangle,
+    RoundedRectangle,
+    Circle,
+    Ellipse,
+    Capsule,
+    Line,
+    Polygon,
+    Star
+);
+
+// Arbitrary geometry, either a path in points relative to the view's origin,
+// or built from the view's laid out rect in pixels, e.g. to plot a chart
+// across it. Strokes are drawn centered on the path whatever their alignment.
+#[derive(AnyView)]
+pub struct Path {
+    view_base: core::Base,
+    style: ShapeStyle,
+    build: Rc<dyn Fn(kurbo::Rect) -> kurbo::BezPath>,
+}
+
+impl Path {
+    // Sized to fit the path, which can be overridden with `size`.
+    pub fn new(path: kurbo::BezPath) -> Self {
+        let bounds = path.bounding_box();
+        let size = (bounds.x1.max(0.0), bounds.y1.max(0.0));
+
+        Path::build(move |rect| {
+            kurbo::Affine::translate(rect.origin().to_vec2())
+                * kurbo::Affine::scale(core::ui_scale())
+                * path.clone()
+        })
+        .size(size.0, size.1)
+    }
+
+    pub fn build(build: impl Fn(kurbo::Rect) -> kurbo::BezPath + 'static) -> Self {
+        Path {
+            view_base: core::Base::default(),
+            style: ShapeStyle::default(),
+            build: Rc::new(build),
+        }
+    }
+}
+
+impl Shape for Path {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        (self.build)(rect)
+    }
+
+    fn aligns_strokes(&self) -> bool {
+        false
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+#[derive(Default, AnyView)]
+pub struct Rectangle {
+    view_base: core::Base,
+    style: ShapeStyle,
+}
+
+impl Shape for Rectangle {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        rect.to_path(0.1)
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+// Rectangle with rounded corners, radii in points are limited to half the
+// shorter side.
+#[derive(AnyView)]
+pub struct RoundedRectangle {
+    view_base: core::Base,
+    style: ShapeStyle,
+    radii: kurbo::RoundedRectRadii,
+}
+
+impl RoundedRectangle {
+    pub fn new(radius: f64) -> Self {
+        RoundedRectangle {
+            view_base: core::Base::default(),
+            style: ShapeStyle::default(),
+            radii: kurbo::RoundedRectRadii::from_single_radius(radius),
+        }
+    }
+
+    pub fn corner_radii(
+        mut self,
+        top_left: f64,
+        top_right: f64,
+        bottom_right: f64,
+        bottom_left: f64,
+    ) -> Self {
+        self.radii = kurbo::RoundedRectRadii::new(top_left, top_right, bottom_right, bottom_left);
+        self
+    }
+}
+
+impl Shape for RoundedRectangle {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        let max = rect.width().min(rect.height()) / 2.0;
+        let radius = |radius: f64| (radius * core::ui_scale()).clamp(0.0, max);
+        let radii = kurbo::RoundedRectRadii::new(
+            radius(self.radii.top_left),
+            radius(self.radii.top_right),
+            radius(self.radii.bottom_right),
+            radius(self.radii.bottom_left),
+        );
+
+        rect.to_rounded_rect(radii).to_path(0.1)
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+// Circle centered in the rect, fitting its shorter side.
+#[derive(Default, AnyView)]
+pub struct Circle {
+    view_base: core::Base,
+    style: ShapeStyle,
+}
+
+impl Circle {
+    pub fn diameter(self, diameter: f64) -> Self {
+        self.size(diameter, diameter)
+    }
+
+    pub fn radius(self, radius: f64) -> Self {
+        self.size(radius * 2.0, radius * 2.0)
+    }
+}
+
+impl Shape for Circle {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        kurbo::Circle::new(rect.center(), rect.width().min(rect.height()) / 2.0).to_path(0.1)
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+#[derive(Default, AnyView)]
+pub struct Ellipse {
+    view_base: core::Base,
+    style: ShapeStyle,
+}
+
+impl Shape for Ellipse {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        kurbo::Ellipse::from_rect(rect).to_path(0.1)
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+// Rectangle with fully rounded ends along its longer side.
+#[derive(Default, AnyView)]
+pub struct Capsule {
+    view_base: core::Base,
+    style: ShapeStyle,
+}
+
+impl Shape for Capsule {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        core::ClipShape::Capsule.path(rect)
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+// Line between two points of the rect, drawn by its stroke only.
+#[derive(AnyView)]
+pub struct Line {
+    view_base: core::Base,
+    style: ShapeStyle,
+    from: core::UnitPoint,
+    to: core::UnitPoint,
+}
+
+impl Line {
+    pub fn new(from: core::UnitPoint, to: core::UnitPoint) -> Self {
+        Line {
+            view_base: core::Base::default(),
+            style: ShapeStyle::default(),
+            from,
+            to,
+        }
+    }
+
+    pub fn horizontal() -> Self {
+        Line::new(core::UnitPoint::LEFT, core::UnitPoint::RIGHT)
+    }
+
+    pub fn vertical() -> Self {
+        Line::new(core::UnitPoint::TOP, core::UnitPoint::BOTTOM)
+    }
+}
+
+impl Shape for Line {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        kurbo::Line::new(self.from.resolve(rect), self.to.resolve(rect)).to_path(0.1)
+    }
+
+    fn closed(&self) -> bool {
+        false
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+// Vertices around the ellipse fitted to the rect, starting at the top and
+// alternating between the outer and the inner radius, relative to the outer.
+fn star_path(rect: kurbo::Rect, points: usize, inner: f64) -> kurbo::BezPath {
+    let center = rect.center();
+    let radii = kurbo::Vec2::new(rect.width() / 2.0, rect.height() / 2.0);
+    let count = if inner < 1.0 { points * 2 } else { points };
+
+    let mut path = kurbo::BezPath::new();
+    for idx in 0..count {
+        let angle = std::f64::consts::TAU * idx as f64 / count as f64;
+        let scale = if idx % 2 == 1 && inner < 1.0 {
+            inner
+        } else {
+            1.0
+        };
+        let vertex =
+            center + kurbo::Vec2::new(angle.sin() * radii.x, -angle.cos() * radii.y) * scale;
+
+        match idx {
+            0 => path.move_to(vertex),
+            _ => path.line_to(vertex),
+        }
+    }
+
+    path.close_path();
+    path
+}
+
+// Regular polygon with at least three sides fitted to the rect.
+#[derive(AnyView)]
+pub struct Polygon {
+    view_base: core::Base,
+    style: ShapeStyle,
+    sides: usize,
+}
+
+impl Polygon {
+    pub fn new(sides: usize) -> Self {
+        Polygon {
+            view_base: core::Base::default(),
+            style: ShapeStyle::default(),
+            sides: sides.max(3),
+        }
+    }
+}
+
+impl Shape for Polygon {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        star_path(rect, self.sides, 1.0)
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+// Star with at least three points fitted to the rect, the inner vertices at
+// the fraction of the outer radius.
+#[derive(AnyView)]
+pub struct Star {
+    view_base: core::Base,
+    style: ShapeStyle,
+    points: usize,
+    inner_radius: f64,
+}
+
+impl Star {
+    pub fn new(points: usize, inner_radius: f64) -> Self {
+        Star {
+            view_base: core::Base::default(),
+            style: ShapeStyle::default(),
+            points: points.max(3),
+            inner_radius: inner_radius.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Shape for Star {
+    fn path(&self, rect: kurbo::Rect) -> kurbo::BezPath {
+        star_path(rect, self.points, self.inner_radius)
+    }
+
+    fn shape_style(&self) -> &ShapeStyle {
+        &self.style
+    }
+
+    fn shape_style_mut(&mut self) -> &mut ShapeStyle {
+        &mut self.style
+    }
+}
+
+// Decoded image fitted into the frame by its content mode, sized to the image
+// in points unless sized otherwise.
+#[derive(AnyView)]
+pub struct Image {
+    view_base: core::Base,
+    image: image::ImageHandle,
+    content_mode: image::ContentMode,
+    quality: image::Quality,
+}
+
+impl Image {
+    pub fn new(image: image::ImageHandle) -> Self {
+        let (width, height) = (image.width() as f64, image.height() as f64);
+
+        Image {
+            view_base: core::Base::default(),
+            image,
+            content_mode: image::ContentMode::default(),
+            quality: image::Quality::default(),
+        }
+        .size(width, height)
+    }
+
+    // Loads a PNG or JPEG file, decoded once across redraws.
+    pub fn load(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        Ok(Image::new(image::ImageHandle::load(path)?))
+    }
+
+    pub fn content_mode(mut self, content_mode: image::ContentMode) -> Self {
+        self.content_mode = content_mode;
+        self
+    }
+
+    pub fn quality(mut self, quality: image::Quality) -> Self {
+        self.quality = quality;
+        self
+    }
+}
+
+impl core::Draw for Image {
+    fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
+        let rect = self.rect();
+        let natural = kurbo::Size::new(self.image.width() as f64, self.image.height() as f64);
+        if natural.is_empty() {
+            return;
+        }
+
+        let frame = self.content_mode.frame(rect, natural);
+        let size = frame.size();
+        let image = self.image.resampled(size.width, size.height, self.quality);
+        let transform = cx.transform
+            * kurbo::Affine::translate(frame.origin().to_vec2())
+            * kurbo::Affine::scale_non_uniform(
+                size.width / image.width as f64,
+                size.height / image.height as f64,
+            );
+
+        let clipped = rect.intersect(frame) != frame;
+        if clipped {
+            scene.push_layer(peniko::Mix::Clip, 1.0, cx.transform, &rect);
+        }
+        scene.draw_image(&image, transform);
+        if clipped {
+            scene.pop_layer();
+        }
+    }
+}
+
+// SVG document fitted into the frame by its content mode, sized to the
+// document in points unless sized otherwise.
+#[derive(AnyView)]
+pub struct SvgImage {
+    view_base: core::Base,
+    svg: svg::SvgHandle,
+    content_mode: image::ContentMode,
+    tint: Option<Color>,
+}
+
+impl SvgImage {
+    pub fn new(svg: svg::SvgHandle) -> Self {
+        let size = svg.size();
+
+        SvgImage {
+            view_base: core::Base::default(),
+            svg,
+            content_mode: image::ContentMode::default(),
+            tint: None,
+        }
+        .size(size.width, size.height)
+    }
+
+    // Loads an SVG file, parsed once across redraws.
+    pub fn load(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        Ok(SvgImage::new(svg::SvgHandle::load(path)?))
+    }
+
+    pub fn content_mode(mut self, content_mode: image::ContentMode) -> Self {
+        self.content_mode = content_mode;
+        self
+    }
+
+    // Paints all fills and strokes in the color, e.g. for monochrome icons.
+    pub fn tint(mut self, color: Color) -> Self {
+        self.tint = Some(color);
+        self
+    }
+}
+
+impl core::Draw for SvgImage {
+    fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
+        let rect = self.rect();
+        let natural = self.svg.size();
+        if natural.is_empty() {
+            return;
+        }
+
+        let frame = self.content_mode.frame(rect, natural);
+        let tint = self
+            .tint
+            .map(|tint| animation::animate(self.id(), "tint", tint));
+        let transform = cx.transform
+            * kurbo::Affine::translate(frame.origin().to_vec2())
+            * kurbo::Affine::scale_non_uniform(
+                frame.width() / natural.width,
+                frame.height() / natural.height,
+            );
+
+        let clipped = rect.intersect(frame) != frame;
+        if clipped {
+            scene.push_layer(peniko::Mix::Clip, 1.0, cx.transform, &rect);
+        }
+        self.svg.render(scene, transform, tint);
+        if clipped {
+            scene.pop_layer();
+        }
+    }
+}
+
+#[derive(AnyView)]
+#[view(layout)]
+pub struct Text {
+    view_base: core::Base,
+    text: String,
+    style: TextStyle,
+    color: peniko::Color,
+    layout: std::cell::RefCell<Option<Rc<text::TextLayout>>>,
+}
+
+impl Text {
+    pub fn new(text: &str) -> Text {
+        Text {
+            view_base: core::Base::default(),
+            text: text.to_string(),
+            style: TextStyle::default(),
+            color: Color::WHITE,
+            layout: std::cell::RefCell::new(None),
+        }
+    }
+
+    pub fn font(mut self, family: &str) -> Self {
+        self.style.family = Some(family.to_string());
+        self
+    }
+
+    pub fn font_size(mut self, size: f64) -> Self {
+        self.style.size = size;
+        self
+    }
+
+    pub fn font_weight(mut self, weight: FontWeight) -> Self {
+        self.style.weight = weight;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    // Size of the shaped text in logical units, wrapped to `max_width` if given.
+    pub fn measure(&self, max_width: Option<f64>) -> kurbo::Size {
+        self.text_layout(max_width.map(|width| width * core::ui_scale()))
+            .map_or(kurbo::Size::ZERO, |layout| layout.size / core::ui_scale())
+    }
+
+    fn text_layout(&self, max_width: Option<f64>) -> Option<Rc<text::TextLayout>> {
+        let style = TextStyle {
+            size: self.style.size * core::ui_scale(),
+            ..self.style.clone()
+        };
+
+        text::layout(&self.text, &style, max_width)
+    }
+}
+
+// Text wraps to the proposed width and ignores the proposed height.
+impl core::Layout for Text {
+    fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+        let content = core::content_proposal(self, proposal);
+        let size = self
+            .text_layout(content.width)
+            .map_or(kurbo::Size::ZERO, |layout| layout.size);
+
+        core::resolve_size(self, proposal, size)
+    }
+
+    fn layout(&self, cx: core::Context, proposal: core::Proposal) {
+        core::place(self, cx, core::measure(self, proposal));
+
+        let content = core::content_proposal(self, proposal);
+        *self.layout.borrow_mut() = self.text_layout(content.width);
+    }
+
+    fn first_baseline(&self, proposal: core::Proposal) -> Option<f64> {
+        let content = core::content_proposal(self, proposal);
+        let layout = self.text_layout(content.width)?;
+        Some(self.padding_top() + layout.lines.first()?.baseline)
+    }
+}
+
+impl core::Draw for Text {
+    fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
+        if let Some(layout) = self.layout.borrow().as_ref() {
+            let color = animation::animate(self.id(), "color", self.color);
+            text::draw(layout, self.origin(), color, cx.transform, scene);
+        }
+    }
+}
+
+// Single line text input editing the bound string while focused.
+#[derive(AnyView)]
+#[view(layout)]
+pub struct TextField {
+    view_base: core::Base,
+    text: state::State<String>,
+    editor: state::State<Editor>,
+    placeholder: String,
+    style: TextStyle,
+    color: peniko::Color,
+    // Shared with the event handlers of the field, which map between
+    // locations and text offsets.
+    layout: Rc<RefCell<Option<Rc<text::TextLayout>>>>,
+    // From window to text layout coordinates, as last drawn.
+    to_text: Rc<Cell<kurbo::Affine>>,
+}
+
+// Applies an edit to copies of the text and editor, and stores them back so
+// they survive rebuilding the view tree.
+fn edit<R>(
+    text: &state::State<String>,
+    editor: &state::State<Editor>,
+    f: impl FnOnce(&mut Editor, &mut String) -> R,
+) -> R {
+    let mut edited_text = text.value();
+    let mut edited = editor.value();
+    edited.clamp(&edited_text);
+
+    let result = f(&mut edited, &mut edited_text);
+
+    if edited_text != text.value() {
+        state::callback(text, move |text| text.clone_from(&edited_text))();
+    }
+    state::callback(editor, move |editor| editor.clone_from(&edited))();
+    result
+}
+
+// Maps an offset into the text shown, which includes the preedit, back to
+// the edited text.
+fn edited_offset(editor: &Editor, offset: usize) -> usize {
+    let preedit = editor
+        .preedit
+        .as_ref()
+        .map_or(0, |(preedit, _)| preedit.len());
+    match offset {
+        offset if offset <= editor.cursor => offset,
+        offset if offset <= editor.cursor + preedit => editor.cursor,
+        offset => offset - preedit,
+    }
+}
+
+impl TextField {
+    #[track_caller]
+    pub fn new(text: &state::State<String>) -> TextField {
+        let editor = state::State::new(Editor::default());
+        let layout = Rc::new(RefCell::new(None::<Rc<text::TextLayout>>));
+        let to_text = Rc::new(Cell::new(kurbo::Affine::IDENTITY));
+
+        let offset_at = {
+            let (layout, to_text) = (layout.clone(), to_text.clone());
+            move |editor: &Editor, location: kurbo::Point| {
+                let offset = layout
+                    .borrow()
+                    .as_ref()
+                    .map_or(0, |layout| layout.offset_at(to_text.get() * location));
+                edited_offset(editor, offset)
+            }
+        };
+
+        let mut view_base = core::Base {
+            focusable: true,
+            ..Default::default()
+        };
+
+        view_base.on_mouse_down = Some(Box::new({
+            let (text, editor, offset_at) = (text.clone(), editor.clone(), offset_at.clone());
+            move |location| {
+                edit(&text, &editor, |editor, text| {
+                    let offset = offset_at(editor, location);
+                    editor.set_cursor(text, offset, false);
+                })
+            }
+        }));
+
+        view_base.on_drag = Some(Box::new({
+            let (text, editor) = (text.clone(), editor.clone());
+            move |drag: event::Drag| {
+                edit(&text, &editor, |editor, text| {
+                    let offset = offset_at(editor, drag.location);
+                    editor.set_cursor(text, offset, true);
+                })
+            }
+        }));
+
+        view_base.on_key_down = Some(Box::new({
+            let (text, editor) = (text.clone(), editor.clone());
+            move |event: &event::KeyEvent| {
+                edit(&text, &editor, |editor, text| {
+                    Self::key_down(editor, text, event)
+                })
+            }
+        }));
+
+        view_base.on_ime = Some(Box::new({
+            let (text, editor) = (text.clone(), editor.clone());
+            move |event: &event::ImeEvent| {
+                edit(&text, &editor, |editor, text| match event {
+                    event::ImeEvent::Preedit(preedit, cursor) => {
+                        editor.set_preedit(preedit, *cursor);
+                    }
+                    event::ImeEvent::Commit(input) => {
+                        editor.set_preedit("", None);
+                        editor.insert(text, input);
+                    }
+                })
+            }
+        }));
+
+        TextField {
+            view_base,
+            text: text.clone(),
+            editor,
+            placeholder: String::new(),
+            style: TextStyle::default(),
+            color: Color::WHITE,
+            layout,
+            to_text,
+        }
+    }
+
+    pub fn placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = placeholder.to_string();
+        self
+    }
+
+    pub fn font(mut self, family: &str) -> Self {
+        self.style.family = Some(family.to_string());
+        self
+    }
+
+    pub fn font_size(mut self, size: f64) -> Self {
+        self.style.size = size;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn key_down(editor: &mut Editor, text: &mut String, event: &event::KeyEvent) -> bool {
+        use editor::Movement;
+        use winit::keyboard::{Key, NamedKey};
+
+        let shortcut = event.modifiers.control_key() || event.modifiers.super_key();
+        let word = event.modifiers.control_key() || event.modifiers.alt_key();
+        let extend = event.modifiers.shift_key();
+
+        let (left, right) = match word {
+            true => (Movement::WordLeft, Movement::WordRight),
+            false => (Movement::Left, Movement::Right),
+        };
+
+        match &event.key {
+            Key::Named(NamedKey::ArrowLeft) => editor.move_cursor(text, left, extend),
+            Key::Named(NamedKey::ArrowRight) => editor.move_cursor(text, right, extend),
+            Key::Named(NamedKey::Home) => editor.move_cursor(text, Movement::Home, extend),
+            Key::Named(NamedKey::End) => editor.move_cursor(text, Movement::End, extend),
+            Key::Named(NamedKey::Backspace) => editor.delete(text, left),
+            Key::Named(NamedKey::Delete) => editor.delete(text, right),
+            Key::Character(key) if shortcut => match key.to_lowercase().as_str() {
+                "a" => editor.select_all(text),
+                "c" if editor.has_selection() => editor::copy(editor.selected_text(text)),
+                "x" => {
+                    if let Some(cut) = editor.cut(text) {
+                        editor::copy(&cut);
+                    }
+                }
+                "v" => {
+                    if let Some(pasted) = editor::paste() {
+                        editor.paste(text, &pasted.replace(['\r', '\n'], " "));
+                    }
+                }
+                "z" if extend => _ = editor.redo(text),
+                "z" => _ = editor.undo(text),
+                "y" => _ = editor.redo(text),
+                _ => return false,
+            },
+            _ => match &event.text {
+                Some(input) if !shortcut && !input.chars().any(char::is_control) => {
+                    editor.insert(text, input)
+                }
+                _ => return false,
+            },
+        }
+
+        true
+    }
+
+    // The text shown, with the IME composition inserted at the cursor.
+    fn display_text(&self) -> String {
+        let mut text = self.text.value();
+        let editor = self.editor.value();
+
+        if let Some((preedit, _)) = &editor.preedit {
+            let mut editor = editor.clone();
+            editor.clamp(&text);
+            text.insert_str(editor.cursor, preedit);
+        }
+
+        text
+    }
+
+    fn style(&self) -> TextStyle {
+        TextStyle {
+            size: self.style.size * core::ui_scale(),
+            ..self.style.clone()
+        }
+    }
+}
+
+// Fills the proposed width, or 200 points without one, and is one line tall.
+impl core::Layout for TextField {
+    fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+        let content = core::content_proposal(self, proposal);
+        let height = text::layout("", &self.style(), None).map_or(0.0, |layout| layout.size.height);
+        let width = content.width.unwrap_or(200.0 * core::ui_scale());
+
+        core::resolve_size(self, proposal, kurbo::Size::new(width, height))
+    }
+
+    fn layout(&self, cx: core::Context, proposal: core::Proposal) {
+        core::place(self, cx, core::measure(self, proposal));
+        *self.layout.borrow_mut() = text::layout(&self.display_text(), &self.style(), None);
+    }
+
+    fn first_baseline(&self, _proposal: core::Proposal) -> Option<f64> {
+        let layout = text::layout(&self.display_text(), &self.style(), None)?;
+        Some(self.padding_top() + layout.lines.first()?.baseline)
+    }
+}
+
+impl core::Draw for TextField {
+    fn draw(&self, cx: core::Context, scene: &mut vello::Scene) {
+        let layout = self.layout.borrow();
+        let Some(layout) = layout.as_ref() else {
+            return;
+        };
+
+        let rect = self.rect();
+        let focused = event::focused() == Some(self.id());
+        let mut editor = self.editor.value();
+        editor.clamp(&self.text.value());
+
+        let preedit = editor.preedit.clone().unwrap_or_default();
+        let caret = editor.cursor + preedit.1.map_or(preedit.0.len(), |(start, _)| start);
+        let caret_x = layout.caret_position(caret).x;
+
+        // Scrolls overflowing text to keep the caret visible.
+        let caret_width = core::ui_scale();
+        let scroll = f64::max(caret_x + caret_width - rect.width(), 0.0);
+        let origin = rect.origin() - kurbo::Vec2::new(scroll, 0.0);
+        self.to_text
+            .set((cx.transform * kurbo::Affine::translate(origin.to_vec2())).inverse());
+
+        scene.push_layer(peniko::Mix::Clip, 1.0, cx.transform, &rect);
+
+        if focused && editor.has_selection() {
+            let selection = editor.selection();
+            let display = |offset: usize| match offset > editor.cursor {
+                true => offset + preedit.0.len(),
+                false => offset,
+            };
+
+            let x0 = layout.caret_position(display(selection.start)).x;
+            let x1 = layout.caret_position(display(selection.end)).x;
+            scene.fill(
+                peniko::Fill::NonZero,
+                cx.transform,
+                Color::rgba8(80, 140, 255, 120),
+                None,
+                &(kurbo::Rect::new(x0, 0.0, x1, layout.line_height()) + origin.to_vec2()),
+            );
+        }
+
+        if self.text.value().is_empty() && preedit.0.is_empty() {
+            let placeholder = text::layout(&self.placeholder, &self.style(), None);
+            if let Some(placeholder) = placeholder {
+                let color = Color::rgb8(122, 122, 122);
+                text::draw(&placeholder, origin, color, cx.transform, scene);
+            }
+        } else {
+            text::draw(layout, origin, self.color, cx.transform, scene);
+        }
+
+        if !preedit.0.is_empty() {
+            let x0 = layout.caret_position(editor.cursor).x;
+            let x1 = layout.caret_position(editor.cursor + preedit.0.len()).x;
+            let y = layout.line_height() - core::ui_scale();
+            scene.stroke(
+                &kurbo::Stroke::new(core::ui_scale()),
+                cx.transform,
+                self.color,
+                None,
+                &(kurbo::Line::new((x0, y), (x1, y)) + origin.to_vec2()),
+            );
+        }
+
+        if focused {
+            let caret = kurbo::Rect::new(caret_x, 0.0, caret_x + caret_width, layout.line_height())
+                + origin.to_vec2();
+            scene.fill(
+                peniko::Fill::NonZero,
+                cx.transform,
+                self.color,
+                None,
+                &caret,
+            );
+            event::set_ime_area(cx.transform.transform_rect_bbox(caret));
+        }
+
+        scene.pop_layer();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Layout;
+    use crate::event;
+    use crate::snapshot::Snapshot;
+
+    #[test]
+    fn rectangle_fill_and_stroke() {
+        let view = Rectangle::default()
+            .size(100.0, 60.0)
+            .fill(Color::rgb8(122, 122, 255))
+            .stroke(Color::rgb8(255, 255, 255), 4.0)
+            .padding_top(20.0)
+            .padding_left(50.0);
+
+        Snapshot::new("rectangle_fill_and_stroke").assert(&view);
+    }
+
+    #[test]
+    fn circle_fill_and_stroke() {
+        let view = Circle::default()
+            .diameter(120.0)
+            .fill(Color::rgb8(255, 122, 122))
+            .stroke(Color::rgb8(255, 255, 255), 6.0)
+            .padding_top(40.0)
+            .padding_left(40.0);
+
+        Snapshot::new("circle_fill_and_stroke").assert(&view);
+    }
+
+    #[test]
+    fn vstack_spacing_and_padding() {
+        let view = VStack::new((
+            Rectangle::default()
+                .size(80.0, 40.0)
+                .fill(Color::rgb8(255, 255, 255)),
+            Circle::default()
+                .diameter(40.0)
+                .fill(Color::rgb8(122, 122, 255))
+                .padding_left(20.0),
+            Rectangle::default()
+                .size(40.0, 40.0)
+                .fill(Color::rgb8(255, 122, 122)),
+        ))
+        .spacing(10.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("vstack_spacing_and_padding").assert(&view);
+    }
+
+    #[test]
+    fn hstack_spacing_and_padding() {
+        let view = HStack::new((
+            Rectangle::default()
+                .size(40.0, 80.0)
+                .fill(Color::rgb8(255, 255, 255)),
+            Circle::default()
+                .diameter(40.0)
+                .fill(Color::rgb8(122, 122, 255))
+                .padding_top(20.0),
+            Rectangle::default()
+                .size(40.0, 40.0)
+                .fill(Color::rgb8(255, 122, 122)),
+        ))
+        .spacing(10.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("hstack_spacing_and_padding").assert(&view);
+    }
+
+    #[test]
+    fn zstack_overlays_children() {
+        let view = ZStack::new((
+            Rectangle::default()
+                .size(100.0, 100.0)
+                .fill(Color::rgba8(255, 255, 255, 122))
+                .stroke(Color::rgb8(255, 255, 255), 2.0),
+            Circle::default()
+                .diameter(50.0)
+                .fill(Color::rgb8(122, 122, 255))
+                .padding_top(25.0)
+                .padding_left(25.0),
+        ))
+        .padding_top(50.0)
+        .padding_left(50.0);
+
+        Snapshot::new("zstack_overlays_children").assert(&view);
+    }
+
+    #[test]
+    fn text_wraps_to_width() {
+        let view = VStack::new((
+            Text::new("Hello, world").font_size(24.0),
+            Text::new("Bold and blue")
+                .font_weight(FontWeight::BOLD)
+                .color(Color::rgb8(122, 122, 255)),
+            Text::new("Wraps long lines at whitespace to fit the width")
+                .width(120.0)
+                .padding_top(10.0),
+        ))
+        .spacing(10.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("text_wraps_to_width").assert(&view);
+    }
+
+    #[test]
+    fn text_measures_lines() {
+        let single = Text::new("Hello").measure(None);
+        let double = Text::new("Hello\nHello").measure(None);
+        assert!(single.width > 0.0);
+        assert_eq!(double.width, single.width);
+        assert!((double.height - 2.0 * single.height).abs() < 1e-6);
+
+        let wrapped = Text::new("Hello Hello").measure(Some(single.width * 1.5));
+        assert!((wrapped.width - single.width).abs() < 1e-6);
+        assert!((wrapped.height - double.height).abs() < 1e-6);
+    }
+
+    #[test]
+    fn stack_distributes_proposal_to_flexible_children() {
+        let view = HStack::new((
+            Rectangle::default().width(50.0),
+            Rectangle::default(),
+            Rectangle::default(),
+        ))
+        .spacing(10.0);
+
+        let size = view.size_that_fits(core::Proposal::new(200.0, 40.0));
+        assert_eq!(size, kurbo::Size::new(200.0, 40.0));
+
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 40.0));
+        let widths: Vec<f64> = view.children().iter().map(|child| child.width()).collect();
+        assert_eq!(widths, vec![50.0, 65.0, 65.0]);
+
+        let ideal = view.size_that_fits(core::Proposal::UNSPECIFIED);
+        assert_eq!(ideal, kurbo::Size::new(70.0, 0.0));
+    }
+
+    // Counts how often it is asked for its size.
+    #[derive(AnyView)]
+    #[view(layout)]
+    struct Measured {
+        view_base: core::Base,
+        count: Rc<Cell<usize>>,
+    }
+
+    impl core::Layout for Measured {
+        fn size_that_fits(&self, proposal: core::Proposal) -> kurbo::Size {
+            self.count.set(self.count.get() + 1);
+            proposal.unwrap_or(kurbo::Size::new(10.0, 10.0))
+        }
+    }
+
+    impl core::Draw for Measured {
+        fn draw(&self, _: core::Context, _: &mut vello::Scene) {}
+    }
+
+    #[test]
+    fn layout_pass_measures_each_proposal_once() {
+        let count = Rc::new(Cell::new(0));
+        let leaf = || Measured {
+            view_base: core::Base::default(),
+            count: count.clone(),
+        };
+        fn nested(view: impl AnyView) -> HStack {
+            HStack::new(VStack::new(view))
+        }
+        let view = nested(nested(nested(nested(leaf()))));
+
+        core::layout_root(
+            &view,
+            core::Context::default(),
+            core::Proposal::new(100.0, 100.0),
+        );
+        let first = count.get();
+        // Without memoizing, the leaf is measured tens of thousands of times.
+        assert!(first <= 10, "measured {first} times");
+
+        view.layout(core::Context::default(), core::Proposal::new(100.0, 100.0));
+        assert_eq!(count.get(), first);
+
+        core::layout_root(
+            &view,
+            core::Context::default(),
+            core::Proposal::new(100.0, 100.0),
+        );
+        assert_eq!(count.get(), 2 * first);
+    }
+
+    #[test]
+    fn stack_proposes_size_to_children() {
+        let view = VStack::new((
+            Rectangle::default().fill(Color::rgb8(122, 122, 255)),
+            Text::new("Text wraps to the width proposed by the stack"),
+            Rectangle::default().fill(Color::rgb8(255, 122, 122)),
+        ))
+        .size(140.0, 160.0)
+        .spacing(10.0)
+        .padding_top(20.0)
+        .padding_left(30.0);
+
+        Snapshot::new("stack_proposes_size_to_children").assert(&view);
+    }
+
+    #[test]
+    fn spacers_push_siblings_apart() {
+        let view = HStack::new((
+            Rectangle::default().size(40.0, 20.0),
+            Spacer::default(),
+            Rectangle::default().size(40.0, 20.0),
+            Spacer::default().min_length(30.0),
+        ));
+
+        view.layout(core::Context::default(), core::Proposal::new(300.0, 100.0));
+        let children = view.children();
+        let widths: Vec<f64> = children.iter().map(|child| child.width()).collect();
+        assert_eq!(widths, vec![40.0, 110.0, 40.0, 110.0]);
+        assert_eq!(children[2].origin(), kurbo::Point::new(150.0, 0.0));
+        assert_eq!(BaseFields::size(&view), kurbo::Size::new(300.0, 20.0));
+
+        let ideal = view.size_that_fits(core::Proposal::UNSPECIFIED);
+        assert_eq!(ideal, kurbo::Size::new(110.0, 20.0));
+
+        let column = VStack::new((Spacer::default(), Text::new("Bottom")));
+        column.layout(core::Context::default(), core::Proposal::new(100.0, 200.0));
+        let children = column.children();
+        assert_eq!(children[0].size().width, 0.0);
+        assert_eq!(children[1].rect().y1, 200.0);
+    }
+
+    #[test]
+    fn spacer_takes_axis_from_proposal() {
+        let spacer = Spacer::default().min_length(10.0);
+        let row = Axis::Horizontal.proposal(Some(80.0), Some(40.0));
+        assert_eq!(spacer.size_that_fits(row), kurbo::Size::new(80.0, 0.0));
+
+        let column = Axis::Vertical.proposal(Some(80.0), Some(40.0));
+        assert_eq!(spacer.size_that_fits(column), kurbo::Size::new(0.0, 80.0));
+
+        // Measured on its own after a stack, it no longer follows that axis.
+        let alone = core::Proposal::new(80.0, 40.0);
+        assert_eq!(spacer.size_that_fits(alone), kurbo::Size::new(80.0, 40.0));
+        let ideal = spacer.size_that_fits(core::Proposal::UNSPECIFIED);
+        assert_eq!(ideal, kurbo::Size::new(10.0, 10.0));
+    }
+
+    #[test]
+    fn frames_clamp_proposals_and_fill_space() {
+        use crate::core::Frame;
+
+        let text = Text::new("Hi").frame(Frame::new().max_width(f64::INFINITY));
+        let fits = text.size_that_fits(core::Proposal::new(200.0, 100.0));
+        assert_eq!(fits.width, 200.0);
+        assert!(text.size_that_fits(core::Proposal::UNSPECIFIED).width < 200.0);
+
+        let bounded = Rectangle::default().frame(
+            Frame::new()
+                .min_width(50.0)
+                .ideal_width(60.0)
+                .max_width(80.0)
+                .max_height(30.0),
+        );
+        let width = |proposal| bounded.size_that_fits(proposal).width;
+        assert_eq!(width(core::Proposal::new(200.0, 100.0)), 80.0);
+        assert_eq!(width(core::Proposal::new(10.0, 100.0)), 50.0);
+        assert_eq!(width(core::Proposal::UNSPECIFIED), 60.0);
+        let height = bounded
+            .size_that_fits(core::Proposal::new(200.0, 100.0))
+            .height;
+        assert_eq!(height, 30.0);
+
+        let capped = || Rectangle::default().frame(Frame::new().max_width(150.0));
+        let view = HStack::new((capped(), capped().layout_priority(1.0)));
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 20.0));
+        let widths: Vec<f64> = view.children().iter().map(|child| child.width()).collect();
+        assert_eq!(widths, vec![50.0, 150.0]);
+
+        let view = HStack::new((capped(), capped()));
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 20.0));
+        let widths: Vec<f64> = view.children().iter().map(|child| child.width()).collect();
+        assert_eq!(widths, vec![100.0, 100.0]);
+    }
+
+    #[test]
+    fn stacks_align_children() {
+        use crate::core::{Alignment, HorizontalAlignment, VerticalAlignment};
+
+        fn origins(view: &(impl Stack + Layout)) -> Vec<kurbo::Point> {
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            view.children().iter().map(|child| child.origin()).collect()
+        }
+
+        let column = |alignment| {
+            VStack::new((
+                Rectangle::default().size(80.0, 20.0),
+                Rectangle::default().size(40.0, 20.0),
+            ))
+            .alignment(alignment)
+        };
+        assert_eq!(origins(&column(HorizontalAlignment::Center))[1].x, 20.0);
+        assert_eq!(origins(&column(HorizontalAlignment::Trailing))[1].x, 40.0);
+
+        let row = HStack::new((
+            Rectangle::default().size(20.0, 80.0),
+            Rectangle::default().size(20.0, 40.0),
+        ))
+        .alignment(VerticalAlignment::Bottom);
+        assert_eq!(origins(&row)[1], kurbo::Point::new(20.0, 40.0));
+
+        let overlay = |alignment| {
+            ZStack::new((
+                Rectangle::default().size(100.0, 100.0),
+                Rectangle::default().size(50.0, 20.0),
+            ))
+            .alignment(alignment)
+        };
+        assert_eq!(
+            origins(&overlay(Alignment::CENTER))[1],
+            kurbo::Point::new(25.0, 40.0)
+        );
+        assert_eq!(
+            origins(&overlay(Alignment::BOTTOM_TRAILING))[1],
+            kurbo::Point::new(50.0, 80.0)
+        );
+
+        // Stacks in flexible frames align their children within the frame.
+        let framed = VStack::new((Rectangle::default().size(40.0, 20.0),))
+            .alignment(HorizontalAlignment::Center)
+            .frame(core::Frame::new().max_width(f64::INFINITY));
+        framed.layout(core::Context::default(), core::Proposal::new(200.0, 100.0));
+        assert_eq!(framed.children()[0].origin().x, 80.0);
+    }
+
+    #[test]
+    fn hstack_aligns_first_baselines() {
+        let view = HStack::new((
+            Text::new("Small").font_size(12.0),
+            Text::new("Large").font_size(32.0),
+        ))
+        .alignment(core::VerticalAlignment::FirstBaseline);
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+
+        let baselines: Vec<f64> = view
+            .children()
+            .iter()
+            .map(|child| {
+                let baseline = child.first_baseline(core::Proposal::UNSPECIFIED).unwrap();
+                child.origin().y + baseline
+            })
+            .collect();
+        assert!(view.children()[0].origin().y > 0.0);
+        assert!((baselines[0] - baselines[1]).abs() < 1e-6);
+
+        let baseline = view.first_baseline(core::Proposal::UNSPECIFIED).unwrap();
+        assert!((baseline - baselines[0]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn alignment_guides_override_defaults() {
+        let view = VStack::new((
+            Rectangle::default()
+                .size(80.0, 20.0)
+                .alignment_guide(core::HorizontalAlignment::Leading, |size| size.width / 4.0),
+            Rectangle::default().size(40.0, 20.0),
+        ));
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+
+        let children = view.children();
+        assert_eq!(children[0].origin().x, 0.0);
+        assert_eq!(children[1].origin().x, 20.0);
+        assert_eq!(BaseFields::size(&view), kurbo::Size::new(80.0, 40.0));
+    }
+
+    #[test]
+    fn stack_alignment() {
+        let view = VStack::new((
+            HStack::new((
+                Text::new("Small").font_size(12.0),
+                Text::new("Large").font_size(32.0),
+                Circle::default()
+                    .diameter(10.0)
+                    .fill(Color::rgb8(122, 122, 255)),
+            ))
+            .spacing(10.0)
+            .alignment(core::VerticalAlignment::FirstBaseline),
+            ZStack::new((
+                Rectangle::default()
+                    .size(100.0, 60.0)
+                    .stroke(Color::rgb8(255, 255, 255), 2.0),
+                Circle::default()
+                    .diameter(30.0)
+                    .fill(Color::rgb8(255, 122, 122)),
+            ))
+            .alignment(core::Alignment::CENTER),
+            Rectangle::default()
+                .size(40.0, 20.0)
+                .fill(Color::rgb8(122, 122, 255)),
+        ))
+        .spacing(10.0)
+        .alignment(core::HorizontalAlignment::Center)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("stack_alignment").assert(&view);
+    }
+
+    #[test]
+    fn grid_sizes_tracks() {
+        let cell = || Rectangle::default().height(20.0);
+        let view = Grid::new((cell(), cell(), cell(), cell()))
+            .columns(vec![
+                GridTrack::Fixed(50.0),
+                GridTrack::flexible(),
+                GridTrack::Fit,
+            ])
+            .rows(vec![GridTrack::Fixed(30.0)])
+            .spacing(10.0);
+
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 100.0));
+        let frames: Vec<kurbo::Rect> = view.children().iter().map(|child| child.rect()).collect();
+        assert_eq!(frames[0], kurbo::Rect::new(0.0, 0.0, 50.0, 20.0));
+        assert_eq!(frames[1], kurbo::Rect::new(60.0, 0.0, 190.0, 20.0));
+        assert_eq!(frames[2].x0, 200.0);
+        assert_eq!(frames[3], kurbo::Rect::new(0.0, 40.0, 50.0, 60.0));
+        assert_eq!(BaseFields::size(&view), kurbo::Size::new(200.0, 60.0));
+
+        let ideal = view.size_that_fits(core::Proposal::UNSPECIFIED);
+        assert_eq!(ideal, kurbo::Size::new(70.0, 60.0));
+
+        let adaptive = Grid::new((cell(), cell(), cell(), cell(), cell()))
+            .columns(vec![GridTrack::adaptive(40.0)])
+            .spacing(10.0);
+        adaptive.layout(core::Context::default(), core::Proposal::new(200.0, 100.0));
+        let children = adaptive.children();
+        let widths: Vec<f64> = children.iter().map(|child| child.width()).collect();
+        assert_eq!(widths, vec![42.5; 5]);
+        assert_eq!(children[3].origin(), kurbo::Point::new(157.5, 0.0));
+        assert_eq!(children[4].origin(), kurbo::Point::new(0.0, 30.0));
+    }
+
+    #[test]
+    fn grid_cells_span_tracks() {
+        let cell = || Rectangle::default();
+        let view = Grid::new((
+            cell().grid_cell_span(2, 1),
+            cell().grid_cell_span(1, 2),
+            cell(),
+            cell(),
+            cell().grid_cell_span(5, 1),
+        ))
+        .columns(vec![GridTrack::Fixed(20.0); 3])
+        .rows(vec![GridTrack::Fixed(20.0); 4]);
+
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+        let frames: Vec<kurbo::Rect> = view.children().iter().map(|child| child.rect()).collect();
+        assert_eq!(frames[0], kurbo::Rect::new(0.0, 0.0, 40.0, 20.0));
+        assert_eq!(frames[1], kurbo::Rect::new(40.0, 0.0, 60.0, 40.0));
+        assert_eq!(frames[2], kurbo::Rect::new(0.0, 20.0, 20.0, 40.0));
+        assert_eq!(frames[3], kurbo::Rect::new(20.0, 20.0, 40.0, 40.0));
+        assert_eq!(frames[4], kurbo::Rect::new(0.0, 40.0, 60.0, 60.0));
+    }
+
+    #[test]
+    fn grid_aligns_cells() {
+        let view = Grid::new((
+            Rectangle::default().size(80.0, 20.0),
+            Rectangle::default().size(20.0, 20.0),
+            Rectangle::default().size(40.0, 40.0),
+            Rectangle::default()
+                .size(20.0, 20.0)
+                .grid_cell_alignment(core::Alignment::BOTTOM_TRAILING),
+        ))
+        .columns(vec![GridTrack::Fit, GridTrack::Fixed(40.0)])
+        .alignment(core::Alignment::CENTER);
+
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+        let origins: Vec<kurbo::Point> =
+            view.children().iter().map(|child| child.origin()).collect();
+        assert_eq!(origins[0], kurbo::Point::new(0.0, 0.0));
+        assert_eq!(origins[1], kurbo::Point::new(90.0, 0.0));
+        assert_eq!(origins[2], kurbo::Point::new(20.0, 20.0));
+        assert_eq!(origins[3], kurbo::Point::new(100.0, 40.0));
+    }
+
+    #[test]
+    fn grid_tracks_and_spans() {
+        let cell = |color| Rectangle::default().fill(color);
+        let view = Grid::new((
+            cell(Color::rgb8(255, 255, 255)).grid_cell_span(2, 1),
+            cell(Color::rgb8(122, 122, 255)).grid_cell_span(1, 2),
+            Circle::default()
+                .diameter(20.0)
+                .fill(Color::rgb8(255, 122, 122))
+                .grid_cell_alignment(core::Alignment::CENTER),
+            Text::new("Cell"),
+            cell(Color::rgb8(122, 255, 122)).grid_cell_span(3, 1),
+        ))
+        .columns(vec![
+            GridTrack::Fixed(40.0),
+            GridTrack::flexible(),
+            GridTrack::Fixed(40.0),
+        ])
+        .rows(vec![GridTrack::Fixed(40.0); 3])
+        .spacing(10.0)
+        .size(160.0, 140.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("grid_tracks_and_spans").assert(&view);
+    }
+
+    #[test]
+    fn explicit_ids_survive_reordering() {
+        let ids = |keys: &[&str]| {
+            let view = HStack::new((Loop::new(0..keys.len() as u32, |idx| {
+                Rectangle::default().width(10.0).key(keys[idx as usize])
+            }),));
+
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            view.children()
+                .iter()
+                .map(|child| child.id())
+                .collect::<Vec<u64>>()
+        };
+
+        let before = ids(&["a", "b", "c"]);
+        let after = ids(&["c", "a", "b"]);
+        assert_eq!(after, vec![before[2], before[0], before[1]]);
+
+        let view = HStack::new((Rectangle::default(), Rectangle::default()));
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+        assert_ne!(view.children()[0].id(), view.children()[1].id());
+    }
+
+    #[test]
+    fn for_each_keeps_state_and_identity_with_items() {
+        struct Row {
+            id: u32,
+            title: &'static str,
+        }
+
+        let build = |rows: &[Row]| {
+            let states = std::cell::RefCell::new(vec![]);
+
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().begin_frame());
+            let view = VStack::new((ForEach::new(
+                rows,
+                |row| row.id,
+                |row| {
+                    states.borrow_mut().push(state::State::new(0));
+                    Text::new(row.title)
+                },
+            ),));
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().end_frame());
+
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            let ids: Vec<u64> = view.children().iter().map(|child| child.id()).collect();
+            (ids, states.into_inner())
+        };
+
+        let (ids, states) = build(&[
+            Row {
+                id: 1,
+                title: "one",
+            },
+            Row {
+                id: 2,
+                title: "two",
+            },
+            Row {
+                id: 3,
+                title: "three",
+            },
+        ]);
+
+        for (idx, state) in states.iter().enumerate() {
+            state::callback(state, move |value| *value = idx + 1)();
+        }
+
+        let (reordered_ids, states) = build(&[
+            Row {
+                id: 3,
+                title: "three",
+            },
+            Row {
+                id: 4,
+                title: "four",
+            },
+            Row {
+                id: 1,
+                title: "one",
+            },
+        ]);
+
+        let values: Vec<usize> = states.iter().map(|state| state.value()).collect();
+        assert_eq!(values, vec![3, 0, 1]);
+        assert_eq!(reordered_ids[0], ids[2]);
+        assert_eq!(reordered_ids[2], ids[0]);
+        assert!(!ids.contains(&reordered_ids[1]));
+    }
+
+    #[test]
+    fn for_each_keys_do_not_collide_with_siblings() {
+        let view = HStack::new((
+            ForEach::new(["a", "b"], |key| *key, |_| Rectangle::default()),
+            ForEach::new(["b", "a"], |key| *key, |_| Rectangle::default()),
+            Rectangle::default().key("a"),
+        ));
+
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+        let ids: std::collections::HashSet<u64> =
+            view.children().iter().map(|child| child.id()).collect();
+        assert_eq!(ids.len(), 5);
+    }
+
+    #[test]
+    fn conditional_sibling_from_one_call_site_keeps_others_state() {
+        let build = |show: bool| {
+            let states = std::cell::RefCell::new(vec![]);
+            let section = |count: u32| {
+                Loop::new(0..count, |_| {
+                    states.borrow_mut().push(state::State::new(0));
+                    Text::new("row")
+                })
+            };
+
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().begin_frame());
+            VStack::new((section(show as u32), section(1)));
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().end_frame());
+            states.into_inner()
+        };
+
+        let states = build(true);
+        state::callback(&states[1], |value| *value = 5)();
+
+        let states = build(false);
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].value(), 5);
+
+        let values: Vec<usize> = build(true).iter().map(|state| state.value()).collect();
+        assert_eq!(values, vec![0, 5]);
+    }
+
+    #[test]
+    fn conditionally_called_helper_keeps_state_of_later_calls() {
+        fn row(states: &RefCell<Vec<state::State<usize>>>) -> Text {
+            states.borrow_mut().push(state::State::new(0));
+            Text::new("row")
+        }
+
+        let build = |show: bool| {
+            let states = RefCell::new(vec![]);
+            state::build(|| {
+                states.borrow_mut().clear();
+                let first = if show { row(&states) } else { Text::new("") };
+                Box::new(VStack::new((first, row(&states))))
+            });
+            states.into_inner()
+        };
+
+        let states = build(true);
+        state::callback(&states[1], |value| *value = 5)();
+
+        let states = build(false);
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].value(), 5);
+
+        let values: Vec<usize> = build(true).iter().map(|state| state.value()).collect();
+        assert_eq!(values, vec![0, 5]);
+    }
+
+    #[test]
+    fn keyed_views_keep_their_state_when_reordered() {
+        fn row(name: &str, states: &RefCell<Vec<(String, state::State<usize>)>>) -> Text {
+            states
+                .borrow_mut()
+                .push((name.to_string(), state::State::new(0)));
+            Text::new(name).key(name)
+        }
+
+        let build = |names: (&str, &str)| {
+            let states = RefCell::new(vec![]);
+            state::build(|| {
+                states.borrow_mut().clear();
+                Box::new(HStack::new((row(names.0, &states), row(names.1, &states))))
+            });
+            states.into_inner()
+        };
+
+        let states = build(("a", "b"));
+        state::callback(&states[0].1, |value| *value = 1)();
+        state::callback(&states[1].1, |value| *value = 2)();
+
+        let values: Vec<(String, usize)> = build(("b", "a"))
+            .into_iter()
+            .map(|(name, state)| (name, state.value()))
+            .collect();
+        assert_eq!(values, vec![("b".to_string(), 2), ("a".to_string(), 1)]);
+    }
+
+    #[test]
+    fn hover_tracks_enter_and_leave_across_rebuilds() {
+        let log = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+
+        let build = || {
+            let (enter, leave, hover, moved) = (log.clone(), log.clone(), log.clone(), log.clone());
+            let view = HStack::new((
+                Rectangle::default()
+                    .size(50.0, 50.0)
+                    .on_mouse_enter(move || enter.borrow_mut().push("enter".to_string()))
+                    .on_mouse_leave(move || leave.borrow_mut().push("leave".to_string()))
+                    .on_hover(move |inside| hover.borrow_mut().push(format!("hover {inside}"))),
+                Rectangle::default()
+                    .size(50.0, 50.0)
+                    .on_mouse_move(move |point| {
+                        moved.borrow_mut().push(format!("move {}", point.x))
+                    }),
+            ));
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            view
+        };
+
+        let at = |x: f64| core::Context {
+            location: kurbo::Point::new(x, 10.0),
+            ..Default::default()
+        };
+
+        assert!(event::mouse_move(&build(), at(10.0)));
+        assert!(!event::mouse_move(&build(), at(20.0)));
+        assert!(event::mouse_move(&build(), at(60.0)));
+        assert!(!event::mouse_leave(&build()));
+        assert!(event::mouse_move(&build(), at(10.0)));
+        assert!(event::mouse_leave(&build()));
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                "enter",
+                "hover true",
+                "leave",
+                "hover false",
+                "move 60",
+                "enter",
+                "hover true",
+                "leave",
+                "hover false"
+            ]
+        );
+    }
+
+    #[test]
+    fn drag_captures_pointer_until_release() {
+        let log = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+
+        let build = || {
+            let drags = log.clone();
+            let view = HStack::new((
+                Rectangle::default()
+                    .size(50.0, 50.0)
+                    .on_drag(move |drag| drags.borrow_mut().push((drag.phase, drag.translation))),
+                Rectangle::default().size(50.0, 50.0),
+            ));
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            view
+        };
+
+        let at = |x: f64| core::Context {
+            location: kurbo::Point::new(x, 10.0),
+            ..Default::default()
+        };
+
+        // Presses outside of a draggable view or without moving don't drag.
+        event::mouse_down(&build(), at(70.0));
+        assert!(!event::mouse_move(&build(), at(80.0)));
+        assert!(!event::mouse_up(&build(), at(80.0)));
+        event::mouse_down(&build(), at(10.0));
+        assert!(!event::mouse_up(&build(), at(10.0)));
+
+        event::mouse_down(&build(), at(10.0));
+        assert!(event::mouse_move(&build(), at(20.0)));
+        assert!(event::mouse_move(&build(), at(90.0)));
+        assert!(event::mouse_up(&build(), at(90.0)));
+        assert!(!event::mouse_move(&build(), at(30.0)));
+
+        let x = |x: f64| kurbo::Vec2::new(x, 0.0);
+        assert_eq!(
+            *log.borrow(),
+            [
+                (event::DragPhase::Started, x(10.0)),
+                (event::DragPhase::Changed, x(80.0)),
+                (event::DragPhase::Ended, x(80.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn focus_follows_tab_order_and_keys_bubble() {
+        use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+        let log = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+        let focused = state::State::new(false);
+
+        let (first, row) = (log.clone(), log.clone());
+        let view = VStack::new((
+            HStack::new((
+                Rectangle::default()
+                    .size(10.0, 10.0)
+                    .focusable(true)
+                    .on_key_down(move |event| {
+                        first.borrow_mut().push("first");
+                        event.key == Key::Character("a".into())
+                    }),
+                Rectangle::default().size(10.0, 10.0).focused(&focused),
+            ))
+            .on_key_down(move |event| {
+                row.borrow_mut().push("row");
+                matches!(event.key, Key::Character(_))
+            }),
+            Rectangle::default().size(10.0, 10.0).focusable(true),
+        ));
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+
+        let row = view.children()[0]
+            .as_any()
+            .downcast_ref::<HStack>()
+            .unwrap();
+        let ids = [
+            row.children()[0].id(),
+            row.children()[1].id(),
+            view.children()[1].id(),
+        ];
+
+        let key = |key: Key, modifiers: ModifiersState| event::KeyEvent {
+            key,
+            text: None,
+            modifiers,
+            repeat: false,
+        };
+        let tab = key(Key::Named(NamedKey::Tab), ModifiersState::empty());
+        let shift_tab = key(Key::Named(NamedKey::Tab), ModifiersState::SHIFT);
+
+        assert!(event::key_down(&view, &tab));
+        assert_eq!(event::focused(), Some(ids[0]));
+        assert!(event::key_down(
+            &view,
+            &key(Key::Character("a".into()), ModifiersState::empty())
+        ));
+        assert!(event::key_down(
+            &view,
+            &key(Key::Character("b".into()), ModifiersState::empty())
+        ));
+        assert_eq!(*log.borrow(), ["first", "first", "row"]);
+
+        event::key_down(&view, &tab);
+        assert_eq!(event::focused(), Some(ids[1]));
+        assert!(focused.value());
+
+        event::key_down(&view, &shift_tab);
+        assert_eq!(event::focused(), Some(ids[0]));
+        assert!(!focused.value());
+
+        state::callback(&focused, |value| *value = true)();
+        event::sync_focus(&view);
+        assert_eq!(event::focused(), Some(ids[1]));
+
+        event::key_down(&view, &tab);
+        event::key_down(&view, &tab);
+        assert_eq!(event::focused(), Some(ids[0]));
+    }
+
+    #[test]
+    fn focus_ring_outlines_focused_view() {
+        let view = HStack::new((
+            Rectangle::default()
+                .size(60.0, 60.0)
+                .fill(Color::rgb8(122, 122, 122))
+                .focusable(true),
+            Rectangle::default()
+                .size(60.0, 60.0)
+                .fill(Color::rgb8(122, 122, 122))
+                .focusable(true),
+        ))
+        .spacing(20.0)
+        .padding_top(70.0)
+        .padding_left(30.0);
+
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 200.0));
+        event::focus_next(&view, true);
+
+        Snapshot::new("focus_ring_outlines_focused_view").assert(&view);
+    }
+
+    fn key(key: &str, modifiers: winit::keyboard::ModifiersState) -> event::KeyEvent {
+        use winit::keyboard::{Key, NamedKey};
+
+        let named = match key {
+            "Backspace" => Some(NamedKey::Backspace),
+            "ArrowLeft" => Some(NamedKey::ArrowLeft),
+            "End" => Some(NamedKey::End),
+            _ => None,
+        };
+
+        event::KeyEvent {
+            key: named.map_or_else(|| Key::Character(key.into()), Key::Named),
+            text: named.is_none().then(|| key.to_string()),
+            modifiers,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn text_field_edits_bound_text() {
+        use winit::keyboard::ModifiersState;
+
+        let text = state::State::new("hello".to_string());
+        let build = || {
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().begin_frame());
+            let view = VStack::new((TextField::new(&text).width(200.0),));
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().end_frame());
+
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            view
+        };
+
+        let type_keys = |keys: &[&str], modifiers: ModifiersState| {
+            for key in keys {
+                assert!(event::key_down(&build(), &self::key(key, modifiers)));
+            }
+        };
+
+        event::mouse_down(
+            &build(),
+            core::Context {
+                location: kurbo::Point::new(190.0, 5.0),
+                ..Default::default()
+            },
+        );
+
+        type_keys(
+            &[" ", "w", "o", "r", "l", "d", "s"],
+            ModifiersState::empty(),
+        );
+        type_keys(&["Backspace"], ModifiersState::empty());
+        assert_eq!(text.value(), "hello world");
+
+        type_keys(&["a", "x"], ModifiersState::CONTROL);
+        assert_eq!(text.value(), "");
+        type_keys(&["v", "v"], ModifiersState::CONTROL);
+        assert_eq!(text.value(), "hello worldhello world");
+        type_keys(&["z"], ModifiersState::CONTROL);
+        assert_eq!(text.value(), "hello world");
+
+        type_keys(
+            &["ArrowLeft"],
+            ModifiersState::CONTROL | ModifiersState::SHIFT,
+        );
+        type_keys(&["c"], ModifiersState::CONTROL);
+        type_keys(&["End", "v"], ModifiersState::CONTROL);
+        assert_eq!(text.value(), "hello worldworld");
+
+        let preedit = event::ImeEvent::Preedit("に".to_string(), Some((3, 3)));
+        assert!(event::ime(&build(), &preedit));
+        assert_eq!(text.value(), "hello worldworld");
+        assert!(event::ime(
+            &build(),
+            &event::ImeEvent::Commit("日本".to_string())
+        ));
+        assert_eq!(text.value(), "hello worldworld日本");
+    }
+
+    #[test]
+    fn text_field_draws_selection_and_placeholder() {
+        use winit::keyboard::ModifiersState;
+
+        let text = state::State::new("hello world".to_string());
+        let empty = state::State::new(String::new());
+        let view = VStack::new((
+            TextField::new(&text).font_size(20.0),
+            TextField::new(&empty).placeholder("Name"),
+        ))
+        .spacing(20.0)
+        .padding_top(20.0)
+        .padding_left(20.0)
+        .padding_right(20.0);
+
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 200.0));
+        event::mouse_down(
+            &view,
+            core::Context {
+                location: kurbo::Point::new(170.0, 30.0),
+                ..Default::default()
+            },
+        );
+        let select_word = key("ArrowLeft", ModifiersState::CONTROL | ModifiersState::SHIFT);
+        event::key_down(&view, &select_word);
+
+        Snapshot::new("text_field_draws_selection_and_placeholder").assert(&view);
+    }
+
+    fn scroll_by(y: f64) -> event::ScrollEvent {
+        event::ScrollEvent {
+            delta: kurbo::Vec2::new(0.0, y),
+            phase: TouchPhase::Moved,
+        }
+    }
+
+    #[test]
+    fn scroll_view_offsets_and_clamps_content() {
+        let build = || {
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().begin_frame());
+            let view = VStack::new((ScrollView::new((VStack::new((Loop::new(0..10, |_| {
+                Rectangle::default().size(50.0, 50.0)
+            }),)),))
+            .height(100.0),));
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().end_frame());
+
+            view.layout(core::Context::default(), core::Proposal::new(200.0, 200.0));
+            view
+        };
+
+        let at = |y: f64| core::Context {
+            location: kurbo::Point::new(10.0, y),
+            ..Default::default()
+        };
+        let first_row = |view: &VStack| {
+            let scroll = view.children()[0]
+                .as_any()
+                .downcast_ref::<ScrollView>()
+                .unwrap();
+            let rows = scroll.children()[0]
+                .as_any()
+                .downcast_ref::<VStack>()
+                .unwrap();
+            rows.children()[0].origin().y
+        };
+
+        assert!(event::scroll(&build(), at(50.0), &scroll_by(-60.0)));
+        assert_eq!(first_row(&build()), -60.0);
+
+        // Scrolling outside of the viewport, or past the end, does nothing.
+        assert!(!event::scroll(&build(), at(150.0), &scroll_by(-60.0)));
+        assert!(event::scroll(&build(), at(50.0), &scroll_by(-1000.0)));
+        assert_eq!(first_row(&build()), -400.0);
+        assert!(!event::scroll(&build(), at(50.0), &scroll_by(-10.0)));
+
+        // Content scrolled out of the viewport can't be hit.
+        let view = build();
+        let scroll = view.children()[0]
+            .as_any()
+            .downcast_ref::<ScrollView>()
+            .unwrap();
+        let rows = scroll.children()[0]
+            .as_any()
+            .downcast_ref::<VStack>()
+            .unwrap();
+        assert!(!rows.children()[7].hit_test(kurbo::Point::new(10.0, 110.0)));
+        assert!(rows.children()[9].hit_test(kurbo::Point::new(10.0, 60.0)));
+    }
+
+    #[test]
+    fn scroll_view_glides_when_laid_out() {
+        let view = ScrollView::new((VStack::new((Loop::new(0..10, |_| {
+            Rectangle::default().size(50.0, 50.0)
+        }),)),))
+        .height(100.0);
+        let first_row = |view: &ScrollView| {
+            let rows = view.children()[0]
+                .as_any()
+                .downcast_ref::<VStack>()
+                .unwrap();
+            rows.children()[0].origin().y
+        };
+
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 200.0));
+        state::callback(&view.scroll, |scroll| {
+            scroll.velocity = kurbo::Vec2::new(0.0, 1000.0);
+            scroll.gliding = true;
+            scroll.last = Some(Instant::now() - std::time::Duration::from_millis(50));
+        })();
+        event::take_frame_request();
+
+        // Drawing only reads the scroll position.
+        core::draw(&view, core::Context::default(), &mut vello::Scene::new());
+        assert_eq!(view.scroll.value().offset, kurbo::Vec2::ZERO);
+        assert!(!event::take_frame_request());
+
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 200.0));
+        let offset = view.scroll.value().offset.y;
+        assert!((50.0..=100.0).contains(&offset));
+        assert_eq!(first_row(&view), -offset);
+        assert!(event::take_frame_request());
+    }
+
+    #[test]
+    fn scroll_view_clips_content_and_draws_indicators() {
+        let view = HStack::new((ScrollView::new((VStack::new((Loop::new(0..8, |idx| {
+            Rectangle::default().size(120.0, 30.0).fill(Color::rgb8(
+                40 + 25 * idx as u8,
+                122,
+                255 - 25 * idx as u8,
+            ))
+        }),))
+        .spacing(10.0),))
+        .size(140.0, 140.0),))
+        .padding_top(30.0)
+        .padding_left(30.0);
+
+        view.layout(core::Context::default(), core::Proposal::new(200.0, 200.0));
+        event::scroll(
+            &view,
+            core::Context {
+                location: kurbo::Point::new(50.0, 50.0),
+                ..Default::default()
+            },
+            &scroll_by(-45.0),
+        );
+
+        Snapshot::new("scroll_view_clips_content_and_draws_indicators").assert(&view);
+    }
+
+    #[test]
+    fn clip_opacity_and_blend_layers() {
+        let stripes = || {
+            HStack::new((Loop::new(0..4, |idx| {
+                Rectangle::default()
+                    .size(20.0, 80.0)
+                    .fill(Color::rgb8(255, 60 * idx as u8, 122))
+            }),))
+        };
+
+        let view = VStack::new((
+            HStack::new((
+                stripes().clip_shape(core::ClipShape::Ellipse),
+                stripes().clip_shape(core::ClipShape::RoundedRect(20.0)),
+            ))
+            .spacing(10.0),
+            ZStack::new((
+                Rectangle::default()
+                    .size(120.0, 40.0)
+                    .fill(Color::rgb8(122, 122, 255)),
+                Rectangle::default()
+                    .size(60.0, 40.0)
+                    .fill(Color::rgb8(255, 255, 255))
+                    .opacity(0.5),
+                Rectangle::default()
+                    .size(30.0, 40.0)
+                    .fill(Color::rgb8(255, 255, 0))
+                    .blend_mode(peniko::Mix::Multiply),
+            )),
+        ))
+        .spacing(20.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("clip_opacity_and_blend_layers").assert(&view);
+    }
+
+    #[test]
+    fn clip_shape_limits_hit_testing() {
+        let clicks = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = clicks.clone();
+
+        let view = ZStack::new((Rectangle::default()
+            .size(100.0, 100.0)
+            .on_click(move || counter.set(counter.get() + 1)),))
+        .clip_shape(core::ClipShape::Ellipse);
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+
+        let click = |x: f64, y: f64| {
+            event::mouse_down(
+                &view,
+                core::Context {
+                    location: kurbo::Point::new(x, y),
+                    ..Default::default()
+                },
+            )
+        };
+
+        click(5.0, 5.0);
+        assert_eq!(clicks.get(), 0);
+        click(50.0, 50.0);
+        assert_eq!(clicks.get(), 1);
+    }
+
+    #[test]
+    fn transforms_offset_rotate_and_scale_subtrees() {
+        let square = |color: Color| Rectangle::default().size(40.0, 40.0).fill(color);
+
+        let view = HStack::new((
+            square(Color::rgb8(255, 122, 122)).offset(0.0, 30.0),
+            ZStack::new((
+                square(Color::rgb8(122, 255, 122)),
+                Circle::default().diameter(10.0).fill(Color::rgb8(0, 0, 0)),
+            ))
+            .rotation(std::f64::consts::FRAC_PI_4, core::UnitPoint::CENTER),
+            square(Color::rgb8(122, 122, 255)).scale_effect(1.5, 0.5, core::UnitPoint::TOP_LEFT),
+        ))
+        .spacing(20.0)
+        .padding_top(60.0)
+        .padding_left(20.0);
+
+        Snapshot::new("transforms_offset_rotate_and_scale_subtrees").assert(&view);
+    }
+
+    #[test]
+    fn transforms_apply_to_hit_testing_but_not_layout() {
+        let view = HStack::new((
+            Rectangle::default().size(40.0, 40.0).offset(100.0, 0.0),
+            Rectangle::default()
+                .size(40.0, 40.0)
+                .rotation(std::f64::consts::FRAC_PI_4, core::UnitPoint::CENTER),
+        ));
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+
+        let children = view.children();
+        assert_eq!(BaseFields::size(&view), kurbo::Size::new(80.0, 40.0));
+        assert_eq!(children[1].origin(), kurbo::Point::new(40.0, 0.0));
+
+        assert!(!children[0].hit_test(kurbo::Point::new(10.0, 10.0)));
+        assert!(children[0].hit_test(kurbo::Point::new(110.0, 10.0)));
+
+        // The rotated square's corners are cut off, and its tips stick out.
+        assert!(!children[1].hit_test(kurbo::Point::new(41.0, 1.0)));
+        assert!(children[1].hit_test(kurbo::Point::new(60.0, -5.0)));
+    }
+
+    #[test]
+    fn with_animation_interpolates_layout_and_completes() {
+        let start = Instant::now();
+        let completed = Rc::new(Cell::new(false));
+
+        let frame = |width: f64, millis: u64| {
+            animation::begin_frame_at(start + std::time::Duration::from_millis(millis));
+            let view = HStack::new((
+                Rectangle::default().size(width, 20.0),
+                Rectangle::default().size(20.0, 20.0),
+            ));
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            animation::end_frame();
+            view
+        };
+
+        frame(20.0, 0);
+        animation::with_animation(
+            animation::Animation::linear(1.0).on_complete({
+                let completed = completed.clone();
+                move || completed.set(true)
+            }),
+            || {},
+        );
+
+        let view = frame(60.0, 0);
+        assert_eq!(view.children()[0].width(), 20.0);
+
+        // Halfway the sibling is laid out at its new position but drawn at
+        // the presented one.
+        let view = frame(60.0, 500);
+        let children = view.children();
+        assert_eq!(children[0].width(), 40.0);
+        assert_eq!(children[1].origin(), kurbo::Point::new(60.0, 0.0));
+        assert!(children[1].hit_test(kurbo::Point::new(45.0, 10.0)));
+        assert!(!completed.get());
+
+        let view = frame(60.0, 1000);
+        assert_eq!(view.children()[0].width(), 60.0);
+        assert!(completed.get());
+    }
+
+    #[test]
+    fn implicit_animation_applies_when_value_changes() {
+        let start = Instant::now();
+
+        let frame = |expanded: bool, millis: u64| {
+            animation::begin_frame_at(start + std::time::Duration::from_millis(millis));
+            let width = if expanded { 100.0 } else { 50.0 };
+            let view = VStack::new((
+                Rectangle::default()
+                    .size(width, 20.0)
+                    .opacity(if expanded { 1.0 } else { 0.5 })
+                    .animation(animation::Animation::ease_in_out(1.0), expanded),
+                Rectangle::default().size(width, 20.0),
+            ));
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            animation::end_frame();
+            view
+        };
+
+        frame(false, 0);
+        frame(true, 0);
+
+        let view = frame(true, 500);
+        let children = view.children();
+        assert!((children[0].width() - 75.0).abs() < 1e-6);
+        assert!((children[0].base().presentation.get().opacity - 0.75).abs() < 1e-6);
+        assert_eq!(children[1].width(), 100.0);
+
+        let view = frame(true, 1000);
+        assert_eq!(view.children()[0].width(), 100.0);
+        assert!(!animation::ANIMATOR.with(|animator| animator.borrow().is_animating()));
+    }
+
+    #[test]
+    fn transitions_keep_removed_views_until_finished() {
+        use transition::{Edge, Transition};
+
+        let start = Instant::now();
+        let stack = |items: &[u8], shown: bool| {
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().begin_frame());
+            let stack = VStack::new((
+                ForEach::new(
+                    items.to_vec(),
+                    |item| *item,
+                    |item| {
+                        Rectangle::default()
+                            .size(160.0, 30.0)
+                            .fill(Color::rgb8(122, 60 * item, 255))
+                            .transition(Transition::Opacity.combined(Transition::Move(Edge::Left)))
+                    },
+                ),
+                Rectangle::default()
+                    .size(160.0, 30.0)
+                    .fill(Color::rgb8(255, 255, 255))
+                    .visible(shown)
+                    .transition(Transition::Scale(0.0)),
+            ))
+            .spacing(10.0)
+            .padding_top(10.0)
+            .padding_left(20.0);
+            state::STATE_MANAGER.with(|manager| manager.borrow_mut().end_frame());
+            stack
+        };
+
+        let at = |millis: u64| start + std::time::Duration::from_millis(millis);
+        let frame = |view: &VStack, millis: u64| {
+            animation::begin_frame_at(at(millis));
+            view.layout(core::Context::default(), core::Proposal::new(200.0, 200.0));
+            core::draw(view, core::Context::default(), &mut vello::Scene::new());
+            animation::end_frame();
+        };
+
+        let view = stack(&[0, 1, 2], true);
+        frame(&view, 0);
+
+        // Like the app, the next tree is built before the previous is dropped.
+        animation::with_animation(animation::Animation::linear(1.0), || {});
+        let next = stack(&[0, 2, 3], false);
+        drop(view);
+        frame(&next, 0);
+        assert_eq!(transition::removing(next.id()), 2);
+
+        let view = stack(&[0, 2, 3], false);
+        drop(next);
+        animation::begin_frame_at(at(500));
+        Snapshot::new("transitions_keep_removed_views_until_finished").assert(&view);
+
+        let next = stack(&[0, 2, 3], false);
+        drop(view);
+        frame(&next, 1000);
+        assert_eq!(transition::removing(next.id()), 0);
+    }
+
+    #[test]
+    fn images_fit_frames_by_content_mode_and_quality() {
+        use crate::image::{ContentMode, ImageHandle, Quality};
+
+        // 4x2 pixels, red on the left and blue on the right.
+        let pixels: Vec<u8> = (0..8)
+            .flat_map(|idx| match idx % 4 < 2 {
+                true => [255, 122, 122, 255],
+                false => [122, 122, 255, 255],
+            })
+            .collect();
+        let wide = ImageHandle::from_rgba(4, 2, pixels).unwrap();
+
+        let checker: Vec<u8> = (0..16)
+            .flat_map(|idx| match (idx % 4 + idx / 4) % 2 == 0 {
+                true => [255, 255, 255, 255],
+                false => [0, 0, 0, 255],
+            })
+            .collect();
+        let checker = ImageHandle::from_rgba(4, 4, checker).unwrap();
+
+        let framed = |mode| {
+            ZStack::new((
+                Rectangle::default()
+                    .size(40.0, 40.0)
+                    .stroke(Color::WHITE, 1.0),
+                Image::new(wide.clone()).size(40.0, 40.0).content_mode(mode),
+            ))
+        };
+
+        let view = VStack::new((
+            HStack::new((
+                framed(ContentMode::Fit),
+                framed(ContentMode::Fill),
+                framed(ContentMode::Stretch),
+                framed(ContentMode::Center),
+            ))
+            .spacing(5.0),
+            HStack::new((
+                Image::new(checker.clone())
+                    .size(60.0, 60.0)
+                    .quality(Quality::Low),
+                Image::new(checker).size(60.0, 60.0),
+            ))
+            .spacing(10.0),
+        ))
+        .spacing(20.0)
+        .padding_top(20.0)
+        .padding_left(15.0);
+
+        Snapshot::new("images_fit_frames_by_content_mode_and_quality").assert(&view);
+    }
+
+    #[test]
+    fn svg_images_render_paths_groups_and_paints() {
+        const ICON: &[u8] = br##"
+            <svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 48 48">
+                <defs>
+                    <linearGradient id="sky" x1="0" y1="0" x2="0" y2="1">
+                        <stop offset="0" stop-color="#ff7a7a"/>
+                        <stop offset="1" stop-color="#7a7aff"/>
+                    </linearGradient>
+                </defs>
+                <circle cx="24" cy="24" r="20" fill="url(#sky)"/>
+                <g transform="translate(24 24) rotate(45)" opacity="0.5">
+                    <rect x="-12" y="-12" width="24" height="24" fill="white"/>
+                </g>
+                <path d="M8 40 L24 28 L40 40" fill="none" stroke="white" stroke-width="4"
+                    stroke-linecap="round" stroke-linejoin="round"/>
+            </svg>
+        "##;
+
+        let icon = svg::SvgHandle::from_static(ICON).unwrap();
+        assert_eq!(icon.size(), kurbo::Size::new(24.0, 24.0));
+
+        let view = VStack::new((
+            HStack::new((
+                SvgImage::new(icon.clone()),
+                SvgImage::new(icon.clone()).size(60.0, 60.0),
+                SvgImage::new(icon.clone())
+                    .size(60.0, 60.0)
+                    .tint(Color::rgb8(122, 255, 122)),
+            ))
+            .spacing(10.0),
+            ZStack::new((
+                Rectangle::default()
+                    .size(120.0, 40.0)
+                    .stroke(Color::WHITE, 1.0),
+                SvgImage::new(icon).size(120.0, 40.0),
+            )),
+        ))
+        .spacing(20.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("svg_images_render_paths_groups_and_paints").assert(&view);
+    }
+
+    #[test]
+    fn gradient_image_and_pattern_paints_follow_the_frame() {
+        let stops = [Color::rgb8(255, 122, 122), Color::rgb8(122, 122, 255)];
+        // 16x16 checkerboard of 8x8 squares.
+        let pixels: Vec<u8> = (0..16 * 16)
+            .flat_map(|idx| match (idx % 16 < 8) == (idx / 16 < 8) {
+                true => [255, 255, 255, 255],
+                false => [122, 122, 255, 255],
+            })
+            .collect();
+        let checker = peniko::Image::new(
+            peniko::Blob::new(std::sync::Arc::new(pixels)),
+            peniko::Format::Rgba8,
+            16,
+            16,
+        );
+
+        let view = VStack::new((
+            HStack::new((
+                Rectangle::default()
+                    .size(120.0, 40.0)
+                    .fill(Paint::linear_gradient(
+                        core::UnitPoint::LEFT,
+                        core::UnitPoint::RIGHT,
+                        stops,
+                    )),
+                Circle::default().diameter(50.0).stroke(
+                    Paint::linear_gradient(core::UnitPoint::TOP, core::UnitPoint::BOTTOM, stops),
+                    6.0,
+                ),
+            ))
+            .spacing(10.0),
+            HStack::new((
+                Rectangle::default()
+                    .size(80.0, 50.0)
+                    .fill(Paint::radial_gradient(core::UnitPoint::CENTER, 0.5, stops)),
+                Circle::default().diameter(50.0).fill(Paint::sweep_gradient(
+                    core::UnitPoint::CENTER,
+                    0.0,
+                    std::f32::consts::TAU,
+                    stops,
+                )),
+            ))
+            .spacing(10.0),
+            HStack::new((
+                Rectangle::default().size(40.0, 40.0).fill(checker.clone()),
+                Rectangle::default()
+                    .size(80.0, 40.0)
+                    .fill(Paint::Pattern(checker.clone()))
+                    .stroke(Color::rgb8(122, 122, 255), 2.0),
+                Circle::default()
+                    .diameter(40.0)
+                    .stroke(Paint::Pattern(checker), 8.0),
+            ))
+            .spacing(10.0),
+        ))
+        .spacing(10.0)
+        .padding_top(10.0)
+        .padding_left(10.0);
+
+        Snapshot::new("gradient_image_and_pattern_paints_follow_the_frame").assert(&view);
+    }
+
+    #[test]
+    fn stroke_styles_dash_cap_join_and_align() {
+        use crate::paint::StrokeAlignment;
+
+        let bordered = |alignment| {
+            Rectangle::default()
+                .size(50.0, 50.0)
+                .fill(Color::rgb8(122, 122, 255))
+                .stroke(
+                    Color::rgba8(255, 255, 255, 200),
+                    StrokeStyle::new(10.0).alignment(alignment),
+                )
+        };
+
+        let view = VStack::new((
+            HStack::new((
+                bordered(StrokeAlignment::Inside),
+                bordered(StrokeAlignment::Center),
+                bordered(StrokeAlignment::Outside),
+            ))
+            .spacing(10.0),
+            HStack::new((
+                Circle::default().diameter(50.0).stroke(
+                    Color::rgb8(255, 122, 122),
+                    StrokeStyle::new(6.0)
+                        .dash([10.0, 8.0], 4.0)
+                        .cap(kurbo::Cap::Round),
+                ),
+                Rectangle::default().size(50.0, 50.0).stroke(
+                    Color::rgb8(255, 255, 255),
+                    StrokeStyle::new(10.0)
+                        .join(kurbo::Join::Round)
+                        .alignment(StrokeAlignment::Inside),
+                ),
+                Rectangle::default().size(50.0, 50.0).stroke(
+                    Color::rgb8(255, 255, 255),
+                    StrokeStyle::new(4.0)
+                        .dash([12.0, 6.0], 0.0)
+                        .join(kurbo::Join::Bevel)
+                        .cap(kurbo::Cap::Square),
+                ),
+            ))
+            .spacing(10.0),
+        ))
+        .spacing(20.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("stroke_styles_dash_cap_join_and_align").assert(&view);
+    }
+
+    #[test]
+    fn aligned_strokes_follow_any_outline() {
+        use crate::paint::StrokeAlignment;
+
+        let star = |alignment| {
+            Star::new(5, 0.5).size(60.0, 60.0).stroke(
+                Color::rgba8(255, 255, 255, 200),
+                StrokeStyle::new(6.0).alignment(alignment),
+            )
+        };
+
+        let view = VStack::new((
+            HStack::new((
+                star(StrokeAlignment::Inside).fill(Color::rgb8(122, 122, 255)),
+                Polygon::new(3)
+                    .size(60.0, 60.0)
+                    .fill(Color::rgb8(122, 122, 255))
+                    .stroke(
+                        Color::rgba8(255, 255, 255, 200),
+                        StrokeStyle::new(6.0).alignment(StrokeAlignment::Outside),
+                    ),
+            ))
+            .spacing(20.0),
+            RoundedRectangle::new(20.0)
+                .size(120.0, 60.0)
+                .fill(Color::rgb8(122, 122, 255))
+                .stroke(
+                    Color::rgba8(255, 255, 255, 200),
+                    StrokeStyle::new(10.0).alignment(StrokeAlignment::Inside),
+                ),
+        ))
+        .spacing(20.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("aligned_strokes_follow_any_outline").assert(&view);
+
+        // Shapes are hit within their outline, and beyond sloped edges only
+        // where the stroke is aligned outside.
+        for alignment in [StrokeAlignment::Inside, StrokeAlignment::Outside] {
+            let view = star(alignment);
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+
+            let path = view.path(view.rect());
+            let (start, end) = match path.elements() {
+                [kurbo::PathEl::MoveTo(start), kurbo::PathEl::LineTo(end), ..] => (*start, *end),
+                _ => panic!("star starts with a line"),
+            };
+            let middle = start.midpoint(end);
+            let edge = (end - start).normalize();
+            let mut outward = kurbo::Vec2::new(-edge.y, edge.x);
+            if outward.dot(middle - view.rect().center()) < 0.0 {
+                outward = -outward;
+            }
+
+            assert!(core::Draw::hit_test(&view, middle - outward * 2.0));
+            let outside = core::Draw::hit_test(&view, middle + outward * 2.0);
+            assert_eq!(outside, alignment == StrokeAlignment::Outside);
+        }
+    }
+
+    #[test]
+    fn shapes_fill_and_stroke_their_geometry() {
+        let fill = Color::rgb8(122, 122, 255);
+        let stroke = Color::rgb8(255, 255, 255);
+
+        let view = VStack::new((
+            HStack::new((
+                RoundedRectangle::new(12.0)
+                    .size(50.0, 40.0)
+                    .fill(fill)
+                    .stroke(stroke, 2.0),
+                RoundedRectangle::new(0.0)
+                    .corner_radii(20.0, 0.0, 20.0, 4.0)
+                    .size(50.0, 40.0)
+                    .fill(fill),
+                Circle::default()
+                    .size(50.0, 40.0)
+                    .fill(fill)
+                    .stroke(stroke, 2.0),
+            ))
+            .spacing(10.0),
+            HStack::new((
+                Ellipse::default()
+                    .size(50.0, 40.0)
+                    .fill(fill)
+                    .stroke(stroke, 2.0),
+                Capsule::default()
+                    .size(50.0, 24.0)
+                    .fill(fill)
+                    .stroke(stroke, 2.0),
+                Line::new(core::UnitPoint::TOP_LEFT, core::UnitPoint::BOTTOM_RIGHT)
+                    .size(50.0, 40.0)
+                    .fill(fill)
+                    .stroke(stroke, StrokeStyle::new(4.0).cap(kurbo::Cap::Round)),
+            ))
+            .spacing(10.0),
+            HStack::new((
+                Polygon::new(6)
+                    .size(50.0, 40.0)
+                    .fill(fill)
+                    .stroke(stroke, 2.0),
+                Polygon::new(3).size(50.0, 40.0).fill(fill),
+                Star::new(5, 0.4)
+                    .size(50.0, 40.0)
+                    .fill(fill)
+                    .stroke(stroke, StrokeStyle::new(2.0).join(kurbo::Join::Round)),
+            ))
+            .spacing(10.0),
+        ))
+        .spacing(15.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("shapes_fill_and_stroke_their_geometry").assert(&view);
+    }
+
+    fn nested_squares() -> kurbo::BezPath {
+        let mut path = kurbo::Rect::new(0.0, 0.0, 50.0, 50.0).to_path(0.1);
+        path.extend(kurbo::Rect::new(15.0, 15.0, 35.0, 35.0).to_path(0.1));
+        path
+    }
+
+    #[test]
+    fn paths_fill_by_rule_and_trim() {
+        let fill = Color::rgb8(122, 122, 255);
+        let values = [0.2, 0.8, 0.4, 0.9, 0.6];
+
+        let chart = move |rect: kurbo::Rect| {
+            let step = rect.width() / (values.len() - 1) as f64;
+            let mut path = kurbo::BezPath::new();
+            for (idx, value) in values.iter().enumerate() {
+                let point =
+                    kurbo::Point::new(rect.x0 + step * idx as f64, rect.y1 - rect.height() * value);
+                match idx {
+                    0 => path.move_to(point),
+                    _ => path.line_to(point),
+                }
+            }
+            path
+        };
+
+        let view = VStack::new((
+            HStack::new((
+                Path::new(nested_squares()).fill(fill),
+                Path::new(nested_squares())
+                    .fill(fill)
+                    .fill_rule(peniko::Fill::EvenOdd)
+                    .stroke(Color::WHITE, 2.0),
+                Circle::default()
+                    .size(50.0, 50.0)
+                    .stroke(Color::WHITE, 4.0)
+                    .trim(0.0, 0.75),
+            ))
+            .spacing(10.0),
+            Path::build(chart)
+                .size(170.0, 60.0)
+                .stroke(
+                    Color::rgb8(255, 122, 122),
+                    StrokeStyle::new(3.0).join(kurbo::Join::Round),
+                )
+                .trim(0.0, 0.6),
+        ))
+        .spacing(20.0)
+        .padding_top(20.0)
+        .padding_left(15.0);
+
+        Snapshot::new("paths_fill_by_rule_and_trim").assert(&view);
+    }
+
+    #[test]
+    fn paths_hit_test_by_rule_and_trim() {
+        let view = HStack::new((
+            Path::new(nested_squares()),
+            Path::new(nested_squares()).fill_rule(peniko::Fill::EvenOdd),
+            Line::horizontal()
+                .size(100.0, 10.0)
+                .stroke(Color::WHITE, 1.0)
+                .trim(0.5, 1.0),
+        ));
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+
+        let children = view.children();
+        assert_eq!(children[0].size(), kurbo::Size::new(50.0, 50.0));
+        assert!(children[0].hit_test(kurbo::Point::new(25.0, 25.0)));
+        assert!(!children[1].hit_test(kurbo::Point::new(75.0, 25.0)));
+        assert!(children[1].hit_test(kurbo::Point::new(55.0, 25.0)));
+
+        assert!(!children[2].hit_test(kurbo::Point::new(120.0, 5.0)));
+        assert!(children[2].hit_test(kurbo::Point::new(180.0, 5.0)));
+    }
+
+    #[test]
+    fn shapes_hit_test_their_animated_trim() {
+        let start = Instant::now();
+        let frame = |to: f64, millis: u64| {
+            animation::begin_frame_at(start + std::time::Duration::from_millis(millis));
+            let view = Line::horizontal()
+                .size(100.0, 10.0)
+                .stroke(Color::WHITE, 1.0)
+                .trim(0.0, to)
+                .animation(animation::Animation::linear(1.0), to.to_bits());
+            view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+            core::draw(&view, core::Context::default(), &mut vello::Scene::new());
+            animation::end_frame();
+            view
+        };
+
+        frame(0.0, 0);
+        frame(1.0, 0);
+
+        // Halfway through, only the drawn half of the line is hit.
+        let view = frame(1.0, 500);
+        assert!(core::Draw::hit_test(&view, kurbo::Point::new(25.0, 5.0)));
+        assert!(!core::Draw::hit_test(&view, kurbo::Point::new(75.0, 5.0)));
+
+        let view = frame(1.0, 1000);
+        assert!(core::Draw::hit_test(&view, kurbo::Point::new(75.0, 5.0)));
+    }
+
+    #[test]
+    fn shapes_hit_test_their_geometry() {
+        let view = HStack::new((
+            Ellipse::default().size(100.0, 50.0),
+            Line::horizontal()
+                .size(100.0, 50.0)
+                .stroke(Color::WHITE, 1.0),
+            Star::new(5, 0.4).size(100.0, 100.0),
+            Rectangle::default().size(100.0, 50.0),
+        ));
+        view.layout(core::Context::default(), core::Proposal::UNSPECIFIED);
+
+        let children = view.children();
+        assert!(!children[0].hit_test(kurbo::Point::new(5.0, 5.0)));
+        assert!(children[0].hit_test(kurbo::Point::new(50.0, 25.0)));
+
+        // Lines are hit near the segment only.
+        assert!(children[1].hit_test(kurbo::Point::new(150.0, 28.0)));
+        assert!(!children[1].hit_test(kurbo::Point::new(150.0, 40.0)));
+
+        // Between the star's points.
+        assert!(!children[2].hit_test(kurbo::Point::new(230.0, 30.0)));
+        assert!(children[2].hit_test(kurbo::Point::new(250.0, 50.0)));
+
+        assert!(children[3].hit_test(kurbo::Point::new(301.0, 1.0)));
+    }
+
+    #[test]
+    fn for_each_renders_items() {
+        let colors = [
+            ("red", Color::rgb8(255, 122, 122)),
+            ("green", Color::rgb8(122, 255, 122)),
+            ("blue", Color::rgb8(122, 122, 255)),
+        ];
+
+        let view = VStack::new((ForEach::new(
+            colors,
+            |(name, _)| *name,
+            |(name, color)| {
+                HStack::new((
+                    Rectangle::default().size(30.0, 30.0).fill(color),
+                    Text::new(name).padding_left(10.0),
+                ))
+            },
+        ),))
+        .spacing(10.0)
+        .padding_top(20.0)
+        .padding_left(20.0);
+
+        Snapshot::new("for_each_renders_items").assert(&view);
+    }
+
+    #[test]
+    fn loop_skips_invisible_elements() {
+        let view = HStack::new((Loop::new(0..6, |idx| {
+            Circle::default()
+                .diameter(20.0)
+                .fill(Color::rgb8(255, 255, 255))
+                .visible(idx % 2 == 0)
+        }),))
+        .spacing(10.0)
+        .padding_top(90.0)
+        .padding_left(10.0);
+
+        Snapshot::new("loop_skips_invisible_elements").assert(&view);
+    }
 }